@@ -0,0 +1,325 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::{self, Read, Write},
+    iter::FusedIterator,
+};
+
+use crate::{Literal, Span, Token, TokenKind};
+
+const MAGIC: &[u8; 4] = b"MLXT";
+
+/// Bumped whenever the encoding changes, so older streams are rejected.
+const VERSION: u8 = 1;
+
+const HAS_STR: u64 = 1;
+const HAS_CHAR: u64 = 2;
+const HAS_SUFFIX: u64 = 4;
+
+/// Encodes tokens in a compact binary stream read back by [`TokenReader`].
+///
+/// After a magic and version header, each token is a sequence of LEB128
+/// varints: its kind, the distance from the end of the previous token to
+/// its start, its length, and its text as an index into a table of strings
+/// that grows as new strings are first written inline.
+pub struct TokenWriter<W: Write> {
+    out: W,
+    strings: HashMap<String, u64>,
+    last_end: usize,
+}
+
+impl<W: Write> TokenWriter<W> {
+    /// Writes the stream header.
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        Ok(Self {
+            out,
+            strings: HashMap::new(),
+            last_end: 0,
+        })
+    }
+
+    pub fn write(&mut self, tok: &Token<'_>) -> io::Result<()> {
+        match tok.kind {
+            TokenKind::Keyword(id) => {
+                self.varint(kind_tag(tok.kind))?;
+                self.varint(id as u64)?;
+            }
+            kind => self.varint(kind_tag(kind))?,
+        }
+
+        let delta = tok.span.start as i64 - self.last_end as i64;
+        self.varint(((delta << 1) ^ (delta >> 63)) as u64)?;
+        self.varint(tok.span.len() as u64)?;
+        self.last_end = tok.span.end;
+
+        self.string(&tok.value)?;
+
+        let mut flags = 0;
+        match tok.literal {
+            Some(Literal::Str(_)) => flags |= HAS_STR,
+            Some(Literal::Char(_)) => flags |= HAS_CHAR,
+            None => {}
+        }
+        if tok.suffix.is_some() {
+            flags |= HAS_SUFFIX;
+        }
+        self.varint(flags)?;
+
+        match &tok.literal {
+            Some(Literal::Str(text)) => self.string(text)?,
+            Some(Literal::Char(ch)) => self.varint(*ch as u64)?,
+            None => {}
+        }
+        if let Some(suffix) = &tok.suffix {
+            self.string(suffix)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the output and returns it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Writes the index of `text` in the string table, followed by the text
+    /// itself the first time it is seen.
+    fn string(&mut self, text: &str) -> io::Result<()> {
+        if let Some(&index) = self.strings.get(text) {
+            return self.varint(index);
+        }
+
+        let index = self.strings.len() as u64;
+        self.strings.insert(text.to_string(), index);
+        self.varint(index)?;
+        self.varint(text.len() as u64)?;
+        self.out.write_all(text.as_bytes())
+    }
+
+    fn varint(&mut self, mut value: u64) -> io::Result<()> {
+        let mut buf = [0; 10];
+        let mut len = 0;
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                buf[len] = byte;
+                len += 1;
+                break;
+            }
+            buf[len] = byte | 0x80;
+            len += 1;
+        }
+        self.out.write_all(&buf[..len])
+    }
+}
+
+/// Decodes a stream written by [`TokenWriter`].
+///
+/// Bytes are read one at a time, so unbuffered readers such as files should
+/// be wrapped in an `io::BufReader`.
+pub struct TokenReader<R: Read> {
+    input: R,
+    strings: Vec<String>,
+    last_end: usize,
+    finished: bool,
+}
+
+impl<R: Read> TokenReader<R> {
+    /// Reads the stream header, failing with `InvalidData` if the input was
+    /// not written by this version of the crate.
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0; 5];
+        input.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a token stream"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid("unsupported token stream version"));
+        }
+
+        Ok(Self {
+            input,
+            strings: vec![],
+            last_end: 0,
+            finished: false,
+        })
+    }
+
+    fn read(&mut self, first: u8) -> io::Result<Token<'static>> {
+        let kind = match kind_from_tag(self.varint_from(first)?) {
+            Some(TokenKind::Keyword(_)) => TokenKind::Keyword(self.varint()? as usize),
+            Some(kind) => kind,
+            None => return Err(invalid("unknown token kind")),
+        };
+
+        let delta = self.varint()?;
+        let delta = (delta >> 1) as i64 ^ -((delta & 1) as i64);
+        let len = self.varint()?;
+        let span = i64::try_from(self.last_end)
+            .ok()
+            .and_then(|last_end| last_end.checked_add(delta))
+            .and_then(|start| usize::try_from(start).ok())
+            .and_then(|start| {
+                let end = usize::try_from(len).ok()?.checked_add(start)?;
+                Some(Span::new(start, end))
+            })
+            .ok_or_else(|| invalid("span out of range"))?;
+        self.last_end = span.end;
+
+        let value = self.string()?;
+        let mut tok = Token::new(kind, Cow::Owned(value), span);
+
+        let flags = self.varint()?;
+        if flags & !(HAS_STR | HAS_CHAR | HAS_SUFFIX) != 0
+            || flags & (HAS_STR | HAS_CHAR) == HAS_STR | HAS_CHAR
+        {
+            return Err(invalid("invalid token flags"));
+        }
+        if flags & HAS_STR != 0 {
+            tok.literal = Some(Literal::Str(Cow::Owned(self.string()?)));
+        } else if flags & HAS_CHAR != 0 {
+            let ch = u32::try_from(self.varint()?)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| invalid("invalid char literal"))?;
+            tok.literal = Some(Literal::Char(ch));
+        }
+        if flags & HAS_SUFFIX != 0 {
+            tok.suffix = Some(Cow::Owned(self.string()?));
+        }
+
+        Ok(tok)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let index = self.varint()? as usize;
+        if let Some(text) = self.strings.get(index) {
+            return Ok(text.clone());
+        }
+        if index != self.strings.len() {
+            return Err(invalid("string index out of range"));
+        }
+
+        let len = self.varint()? as usize;
+        let mut bytes = vec![];
+        self.input
+            .by_ref()
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+        if bytes.len() != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let text = String::from_utf8(bytes).map_err(|_| invalid("invalid UTF-8 in string"))?;
+        self.strings.push(text.clone());
+        Ok(text)
+    }
+
+    /// Reads a byte, or `None` at the end of the input.
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        loop {
+            match self.input.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        match self.byte()? {
+            Some(first) => self.varint_from(first),
+            None => Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+
+    fn varint_from(&mut self, first: u8) -> io::Result<u64> {
+        let mut value = u64::from(first & 0x7f);
+        let mut byte = first;
+        let mut shift = 0;
+        while byte & 0x80 != 0 {
+            shift += 7;
+            if shift > 63 {
+                return Err(invalid("varint too long"));
+            }
+            byte = self.byte()?.ok_or(io::ErrorKind::UnexpectedEof)?;
+            // Only the lowest bit of the tenth byte fits in a `u64`.
+            if shift == 63 && byte > 0x01 {
+                return Err(invalid("varint too long"));
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+        }
+        Ok(value)
+    }
+}
+
+impl<R: Read> Iterator for TokenReader<R> {
+    type Item = io::Result<Token<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = match self.byte() {
+            Ok(Some(first)) => self.read(first),
+            Ok(None) => {
+                self.finished = true;
+                return None;
+            }
+            Err(err) => Err(err),
+        };
+        self.finished = result.is_err();
+        Some(result)
+    }
+}
+
+impl<R: Read> FusedIterator for TokenReader<R> {}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn kind_tag(kind: TokenKind) -> u64 {
+    match kind {
+        TokenKind::Whitespace => 0,
+        TokenKind::Invalid => 1,
+        TokenKind::Null => 2,
+        TokenKind::Int => 3,
+        TokenKind::Float => 4,
+        TokenKind::Identifier => 5,
+        TokenKind::Keyword(_) => 6,
+        TokenKind::Ponct => 7,
+        TokenKind::Op => 8,
+        TokenKind::String => 9,
+        TokenKind::Char => 10,
+        TokenKind::Comment => 11,
+        TokenKind::DocComment => 12,
+        TokenKind::Newline => 13,
+    }
+}
+
+fn kind_from_tag(tag: u64) -> Option<TokenKind> {
+    Some(match tag {
+        0 => TokenKind::Whitespace,
+        1 => TokenKind::Invalid,
+        2 => TokenKind::Null,
+        3 => TokenKind::Int,
+        4 => TokenKind::Float,
+        5 => TokenKind::Identifier,
+        6 => TokenKind::Keyword(0),
+        7 => TokenKind::Ponct,
+        8 => TokenKind::Op,
+        9 => TokenKind::String,
+        10 => TokenKind::Char,
+        11 => TokenKind::Comment,
+        12 => TokenKind::DocComment,
+        13 => TokenKind::Newline,
+        _ => return None,
+    })
+}
@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use unicode_xid::UnicodeXID;
+
+pub type CharClass = fn(char) -> bool;
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub struct StringDelimiter {
+    pub quote: char,
+    pub raw: bool,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct BlockComment {
+    pub open: String,
+    pub close: String,
+    pub nested: bool,
+}
+
+/// How line breaks are lexed. `\r\n` always counts as a single line break.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum Newlines {
+    /// Part of the surrounding `Whitespace` token.
+    #[default]
+    Whitespace,
+    /// One `Newline` token per line break.
+    Split,
+    /// One `Newline` token per run of line breaks, spanning blank lines.
+    Merge,
+}
+
+/// Lexical rules of a [`Lexer`](crate::Lexer): character classes,
+/// operators, string and comment delimiters, keywords, and how newlines,
+/// errors and the end of input are reported.
+#[derive(Clone, Debug)]
+pub struct LexerConfig {
+    pub(crate) ident_start: CharClass,
+    pub(crate) ident_continue: CharClass,
+    pub(crate) whitespace: CharClass,
+    pub(crate) operator: CharClass,
+    pub(crate) operators: Vec<String>,
+    pub(crate) strings: Vec<StringDelimiter>,
+    pub(crate) char_quote: Option<char>,
+    pub(crate) line_comments: Vec<String>,
+    pub(crate) doc_comments: Vec<String>,
+    pub(crate) block_comments: Vec<BlockComment>,
+    pub(crate) emit_comments: bool,
+    pub(crate) keywords: HashMap<String, usize>,
+    pub(crate) emit_eof: bool,
+    pub(crate) recover: bool,
+    pub(crate) leading_dot_floats: bool,
+    pub(crate) newlines: Newlines,
+}
+
+impl Default for LexerConfig {
+    fn default() -> Self {
+        Self {
+            ident_start: |ch| ch == '_' || ch.is_xid_start(),
+            ident_continue: UnicodeXID::is_xid_continue,
+            whitespace: |ch| ch.is_ascii_whitespace(),
+            operator: |_| false,
+            operators: vec![],
+            strings: vec![StringDelimiter {
+                quote: '"',
+                raw: false,
+            }],
+            char_quote: Some('\''),
+            line_comments: vec![],
+            doc_comments: vec![],
+            block_comments: vec![],
+            emit_comments: false,
+            keywords: HashMap::new(),
+            emit_eof: false,
+            recover: false,
+            leading_dot_floats: false,
+            newlines: Newlines::Whitespace,
+        }
+    }
+}
+
+impl LexerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ident_start(mut self, class: CharClass) -> Self {
+        self.ident_start = class;
+        self
+    }
+
+    pub fn ident_continue(mut self, class: CharClass) -> Self {
+        self.ident_continue = class;
+        self
+    }
+
+    /// Restricts identifiers to `[A-Za-z_][A-Za-z0-9_]*`.
+    pub fn ascii_identifiers(self) -> Self {
+        self.ident_start(|ch| ch.is_ascii_alphabetic() || ch == '_')
+            .ident_continue(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+    }
+
+    pub fn whitespace(mut self, class: CharClass) -> Self {
+        self.whitespace = class;
+        self
+    }
+
+    /// Characters matching `class` are emitted as single-character `Op` tokens.
+    pub fn operator(mut self, class: CharClass) -> Self {
+        self.operator = class;
+        self
+    }
+
+    /// Registers multi-character operators, matched longest first.
+    pub fn operators<I, S>(mut self, operators: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.operators.extend(
+            operators
+                .into_iter()
+                .map(Into::into)
+                .filter(|op| !op.is_empty()),
+        );
+        self.operators
+            .sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        self.operators.dedup();
+        self
+    }
+
+    /// Strings quoted with `quote` decode backslash escapes.
+    pub fn string(self, quote: char) -> Self {
+        self.string_delimiter(StringDelimiter { quote, raw: false })
+    }
+
+    /// Strings quoted with `quote` are taken verbatim, without escapes.
+    pub fn raw_string(self, quote: char) -> Self {
+        self.string_delimiter(StringDelimiter { quote, raw: true })
+    }
+
+    pub fn no_strings(mut self) -> Self {
+        self.strings.clear();
+        self
+    }
+
+    /// Single characters quoted with `quote` are lexed as `Char` tokens.
+    /// A quote not closing a character literal is still a `Ponct` token.
+    pub fn char_literal(mut self, quote: char) -> Self {
+        self.char_quote = Some(quote);
+        self
+    }
+
+    pub fn no_char_literals(mut self) -> Self {
+        self.char_quote = None;
+        self
+    }
+
+    /// Comments starting with `prefix` run to the end of the line.
+    pub fn line_comment(mut self, prefix: impl Into<String>) -> Self {
+        self.line_comments.push(prefix.into());
+        self
+    }
+
+    /// Line comments starting with `prefix` are emitted as `DocComment`.
+    pub fn doc_comment(mut self, prefix: impl Into<String>) -> Self {
+        self.doc_comments.push(prefix.into());
+        self
+    }
+
+    pub fn block_comment(mut self, open: impl Into<String>, close: impl Into<String>) -> Self {
+        self.block_comments.push(BlockComment {
+            open: open.into(),
+            close: close.into(),
+            nested: false,
+        });
+        self
+    }
+
+    pub fn nested_block_comment(
+        mut self,
+        open: impl Into<String>,
+        close: impl Into<String>,
+    ) -> Self {
+        self.block_comments.push(BlockComment {
+            open: open.into(),
+            close: close.into(),
+            nested: true,
+        });
+        self
+    }
+
+    /// Emits comments as tokens instead of skipping them.
+    pub fn emit_comments(mut self, emit: bool) -> Self {
+        self.emit_comments = emit;
+        self
+    }
+
+    /// Identifiers equal to one of `keywords` are emitted as `Keyword(id)`,
+    /// where `id` is the keyword's index among the distinct keywords
+    /// registered so far; repeating a keyword keeps its first id.
+    pub fn keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for keyword in keywords {
+            let id = self.keywords.len();
+            self.keywords.entry(keyword.into()).or_insert(id);
+        }
+        self
+    }
+
+    /// Ends the token stream with a `Null` token at the end of input.
+    pub fn emit_eof(mut self, emit: bool) -> Self {
+        self.emit_eof = emit;
+        self
+    }
+
+    /// Keeps lexing after malformed input instead of stopping at the first
+    /// `Invalid` token.
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    /// Lexes `.5` as a `Float` rather than `.` followed by `5`.
+    pub fn leading_dot_floats(mut self, enable: bool) -> Self {
+        self.leading_dot_floats = enable;
+        self
+    }
+
+    /// Emits line breaks as `Newline` tokens, whatever the whitespace class.
+    pub fn newlines(mut self, newlines: Newlines) -> Self {
+        self.newlines = newlines;
+        self
+    }
+
+    /// Longest registered operator or comment delimiter, in bytes.
+    pub(crate) fn longest_delimiter(&self) -> usize {
+        let operators = self.operators.iter();
+        let comments = self.line_comments.iter().chain(&self.doc_comments);
+        let blocks = self.block_comments.iter().flat_map(|c| [&c.open, &c.close]);
+
+        operators
+            .chain(comments)
+            .chain(blocks)
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn string_delimiter(mut self, delimiter: StringDelimiter) -> Self {
+        self.strings.retain(|d| d.quote != delimiter.quote);
+        self.strings.push(delimiter);
+        self
+    }
+}
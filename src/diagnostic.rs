@@ -0,0 +1,273 @@
+use core::fmt::Write;
+
+use crate::{LexError, SourceMap, Span};
+
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note => "note",
+            Level::Help => "help",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Level::Error => "\x1b[1;31m",
+            Level::Warning => "\x1b[1;33m",
+            Level::Note => "\x1b[1;32m",
+            Level::Help => "\x1b[1;36m",
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+/// A message about the source, pointing at it through labelled spans.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub code: Option<String>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(level: Level, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            code: None,
+            message: message.into(),
+            labels: vec![],
+            notes: vec![],
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Level::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Level::Warning, message)
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Underlines `span` with `^` carets.
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    /// Underlines `span` with `-` dashes.
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+impl From<&LexError> for Diagnostic {
+    fn from(err: &LexError) -> Self {
+        Diagnostic::error(err.message())
+            .with_code(err.code())
+            .with_label(err.span(), err.message())
+    }
+}
+
+impl From<LexError> for Diagnostic {
+    fn from(err: LexError) -> Self {
+        Diagnostic::from(&err)
+    }
+}
+
+/// Renders diagnostics as rustc-style source snippets:
+///
+/// ```text
+/// error[E0004]: invalid escape sequence
+///  --> main.src:1:4
+///   |
+/// 1 | a "\q"
+///   |    ^^ invalid escape sequence
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+    colors: bool,
+    origin: Option<String>,
+}
+
+const TAB_WIDTH: usize = 4;
+const GUTTER: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables ANSI colors in the output.
+    pub fn colors(mut self, colors: bool) -> Self {
+        self.colors = colors;
+        self
+    }
+
+    /// Name of the source shown after `-->`, usually its path.
+    pub fn origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, map: &SourceMap<'_>) -> String {
+        let mut out = String::new();
+        let level = diagnostic.level;
+
+        out.push_str(&self.paint(level.color(), level.name()));
+        if let Some(code) = &diagnostic.code {
+            out.push_str(&self.paint(level.color(), &format!("[{}]", code)));
+        }
+        out.push_str(&self.paint(BOLD, &format!(": {}", diagnostic.message)));
+        out.push('\n');
+
+        let mut labels: Vec<_> = diagnostic.labels.iter().collect();
+        labels.sort_by_key(|label| (label.span.start, !label.primary));
+
+        let width = labels
+            .iter()
+            .map(|label| map.line(label.span.start).to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(width);
+
+        if let Some(first) = labels.iter().find(|label| label.primary).or(labels.first()) {
+            let loc = map.line_col(first.span.start);
+            let _ = match &self.origin {
+                Some(origin) => writeln!(out, "{}{} {}:{}", pad, self.arrow(), origin, loc),
+                None => writeln!(out, "{}{} {}", pad, self.arrow(), loc),
+            };
+        } else if let Some(origin) = &self.origin {
+            let _ = writeln!(out, "{}{} {}", pad, self.arrow(), origin);
+        }
+
+        if !labels.is_empty() {
+            let _ = writeln!(out, "{} {}", pad, self.paint(GUTTER, "|"));
+        }
+
+        let mut previous = None;
+        for label in &labels {
+            let line = map.line(label.span.start);
+            let line_span = map.line_span(line);
+            let bytes = line_span.slice(map.source());
+            let text = String::from_utf8_lossy(bytes);
+
+            if previous != Some(line) {
+                if previous.is_some_and(|prev| line > prev + 1) {
+                    let _ = writeln!(out, "{}", self.paint(GUTTER, "..."));
+                }
+                let number = format!("{:>width$} |", line, width = width);
+                let _ = writeln!(
+                    out,
+                    "{} {}",
+                    self.paint(GUTTER, &number),
+                    expand_tabs(&text)
+                );
+                previous = Some(line);
+            }
+
+            // Labels may point at a line terminator or past the end of input.
+            let start = label.span.start.min(line_span.end);
+            let end = label.span.end.clamp(start, line_span.end) - line_span.start;
+            let start = start - line_span.start;
+            let offset = display_width(&bytes[..start]);
+            let length = display_width(&bytes[start..end]);
+
+            let (mark, color) = if label.primary {
+                ('^', level.color())
+            } else {
+                ('-', GUTTER)
+            };
+            let mut underline = mark.to_string().repeat(length.max(1));
+            if !label.message.is_empty() {
+                underline.push(' ');
+                underline.push_str(&label.message);
+            }
+            let _ = writeln!(
+                out,
+                "{} {} {}{}",
+                pad,
+                self.paint(GUTTER, "|"),
+                " ".repeat(offset),
+                self.paint(color, &underline)
+            );
+        }
+
+        if !diagnostic.notes.is_empty() {
+            if !labels.is_empty() {
+                let _ = writeln!(out, "{} {}", pad, self.paint(GUTTER, "|"));
+            }
+            for note in &diagnostic.notes {
+                let _ = writeln!(out, "{} {} note: {}", pad, self.paint(GUTTER, "="), note);
+            }
+        }
+
+        out
+    }
+
+    fn arrow(&self) -> String {
+        self.paint(GUTTER, "-->")
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.colors {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Columns taken by `bytes` once printed, counting characters.
+fn display_width(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            b'\t' => TAB_WIDTH,
+            _ if b & 0xc0 == 0x80 => 0,
+            _ => 1,
+        })
+        .sum()
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
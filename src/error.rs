@@ -0,0 +1,135 @@
+use core::fmt;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::Span;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum LexError {
+    UnexpectedChar(Span),
+    UnterminatedString(Span),
+    UnterminatedComment(Span),
+    InvalidEscape(Span),
+    InvalidChar(Span),
+    InvalidUtf8(Span),
+    NumberOverflow(Span),
+    MalformedExponent(Span),
+    MissingDigits(Span),
+    InvalidDigit(Span),
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match *self {
+            LexError::UnexpectedChar(span)
+            | LexError::UnterminatedString(span)
+            | LexError::UnterminatedComment(span)
+            | LexError::InvalidEscape(span)
+            | LexError::InvalidChar(span)
+            | LexError::InvalidUtf8(span)
+            | LexError::NumberOverflow(span)
+            | LexError::MalformedExponent(span)
+            | LexError::MissingDigits(span)
+            | LexError::InvalidDigit(span) => span,
+        }
+    }
+
+    pub(crate) fn shift(self, offset: usize) -> Self {
+        let span = self.span().shift(offset);
+        match self {
+            LexError::UnexpectedChar(_) => LexError::UnexpectedChar(span),
+            LexError::UnterminatedString(_) => LexError::UnterminatedString(span),
+            LexError::UnterminatedComment(_) => LexError::UnterminatedComment(span),
+            LexError::InvalidEscape(_) => LexError::InvalidEscape(span),
+            LexError::InvalidChar(_) => LexError::InvalidChar(span),
+            LexError::InvalidUtf8(_) => LexError::InvalidUtf8(span),
+            LexError::NumberOverflow(_) => LexError::NumberOverflow(span),
+            LexError::MalformedExponent(_) => LexError::MalformedExponent(span),
+            LexError::MissingDigits(_) => LexError::MissingDigits(span),
+            LexError::InvalidDigit(_) => LexError::InvalidDigit(span),
+        }
+    }
+
+    /// Stable identifier of the error kind, e.g. `E0001`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexError::UnexpectedChar(_) => "E0001",
+            LexError::UnterminatedString(_) => "E0002",
+            LexError::UnterminatedComment(_) => "E0003",
+            LexError::InvalidEscape(_) => "E0004",
+            LexError::InvalidChar(_) => "E0005",
+            LexError::InvalidUtf8(_) => "E0006",
+            LexError::NumberOverflow(_) => "E0007",
+            LexError::MalformedExponent(_) => "E0008",
+            LexError::MissingDigits(_) => "E0009",
+            LexError::InvalidDigit(_) => "E0010",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            LexError::UnexpectedChar(_) => "unexpected character",
+            LexError::UnterminatedString(_) => "unterminated string literal",
+            LexError::UnterminatedComment(_) => "unterminated block comment",
+            LexError::InvalidEscape(_) => "invalid escape sequence",
+            LexError::InvalidChar(_) => "malformed character literal",
+            LexError::InvalidUtf8(_) => "invalid UTF-8 sequence",
+            LexError::NumberOverflow(_) => "number literal out of range",
+            LexError::MalformedExponent(_) => "malformed exponent",
+            LexError::MissingDigits(_) => "missing digits after radix prefix",
+            LexError::InvalidDigit(_) => "invalid digit for the literal's radix",
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message(), self.span())
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// Failure to open a file for lexing, with the path that was opened.
+#[derive(Debug)]
+pub struct FileError {
+    path: PathBuf,
+    error: io::Error,
+}
+
+impl FileError {
+    pub(crate) fn new(path: &Path, error: io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn io_error(&self) -> &io::Error {
+        &self.error
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<FileError> for io::Error {
+    fn from(err: FileError) -> Self {
+        io::Error::new(err.error.kind(), err)
+    }
+}
@@ -0,0 +1,57 @@
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, Read},
+    ops::Deref,
+    path::Path,
+};
+
+use memmap2::Mmap;
+
+/// Bytes the lexer reads from: borrowed, owned or mapped from a file.
+pub(crate) enum Input<'a> {
+    Borrowed(&'a [u8]),
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Input<'static> {
+    /// Maps the file at `path`, reading it instead when it is not a regular
+    /// file (pipes, character devices) or is empty.
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let metadata = file.metadata()?;
+
+        if metadata.is_file() && metadata.len() > 0 {
+            // SAFETY: the map is read-only; like any mmap user we rely on the
+            // file not being truncated while it is lexed.
+            let map = unsafe { Mmap::map(&file)? };
+            return Ok(Input::Mapped(map));
+        }
+
+        let mut data = vec![];
+        file.read_to_end(&mut data)?;
+        Ok(Input::Owned(data))
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for Input<'a> {
+    fn from(input: Cow<'a, [u8]>) -> Self {
+        match input {
+            Cow::Borrowed(input) => Input::Borrowed(input),
+            Cow::Owned(input) => Input::Owned(input),
+        }
+    }
+}
+
+impl Deref for Input<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Input::Borrowed(input) => input,
+            Input::Owned(input) => input,
+            Input::Mapped(map) => map,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use core::fmt::Write as _;
+use std::io::{self, Write};
+
+use crate::{Literal, SourceMap, Token, TokenKind};
+
+/// Writes tokens as a JSON array, or as JSON Lines with one token object
+/// per line:
+///
+/// ```text
+/// {"kind":"Int","text":"0x1F","span":[4,8],"line":1,"col":5,"value":31}
+/// ```
+///
+/// `value` holds the decoded literal of strings, chars and numbers, and is
+/// `null` for other tokens or numbers out of range.
+pub struct JsonWriter<W: Write> {
+    out: W,
+    lines: bool,
+    origin: Option<String>,
+    written: usize,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// Writes a single JSON array, closed by [`JsonWriter::finish`].
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            lines: false,
+            origin: None,
+            written: 0,
+        }
+    }
+
+    /// Writes one JSON object per line.
+    pub fn lines(out: W) -> Self {
+        Self {
+            lines: true,
+            ..Self::new(out)
+        }
+    }
+
+    /// Adds a `file` field to the tokens written from now on.
+    pub fn set_origin(&mut self, origin: Option<String>) {
+        self.origin = origin;
+    }
+
+    pub fn write(&mut self, tok: &Token<'_>, map: &SourceMap<'_>) -> io::Result<()> {
+        let object = self.object(tok, map);
+        let separator = match (self.lines, self.written) {
+            (true, _) => "",
+            (false, 0) => "[\n",
+            (false, _) => ",\n",
+        };
+        let terminator = if self.lines { "\n" } else { "" };
+        self.written += 1;
+
+        write!(self.out, "{}{}{}", separator, object, terminator)
+    }
+
+    /// Closes the array and flushes the output.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.lines {
+            let close = if self.written == 0 { "[]\n" } else { "\n]\n" };
+            self.out.write_all(close.as_bytes())?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn object(&self, tok: &Token<'_>, map: &SourceMap<'_>) -> String {
+        let mut out = String::from("{");
+        if let Some(origin) = &self.origin {
+            out.push_str("\"file\":");
+            push_str(&mut out, origin);
+            out.push(',');
+        }
+
+        let loc = map.line_col(tok.span.start);
+        let _ = write!(out, "\"kind\":\"{}\",", tok.kind.name());
+        if let TokenKind::Keyword(id) = tok.kind {
+            let _ = write!(out, "\"keyword\":{},", id);
+        }
+        out.push_str("\"text\":");
+        push_str(&mut out, &tok.value);
+        let _ = write!(
+            out,
+            ",\"span\":[{},{}],\"line\":{},\"col\":{},\"value\":",
+            tok.span.start, tok.span.end, loc.line, loc.col
+        );
+        push_value(&mut out, tok);
+        if let Some(suffix) = &tok.suffix {
+            out.push_str(",\"suffix\":");
+            push_str(&mut out, suffix);
+        }
+        out.push('}');
+        out
+    }
+}
+
+fn push_value(out: &mut String, tok: &Token<'_>) {
+    match (&tok.literal, tok.kind) {
+        (Some(Literal::Str(text)), _) => push_str(out, text),
+        (Some(Literal::Char(ch)), _) => push_str(out, ch.encode_utf8(&mut [0; 4])),
+        (None, TokenKind::Int) => match tok.int_value().map(|value| value.to_u128()) {
+            Some(Ok(value)) => {
+                let _ = write!(out, "{}", value);
+            }
+            _ => out.push_str("null"),
+        },
+        (None, TokenKind::Float) => match tok.as_f64() {
+            Some(Ok(value)) if value.is_finite() => {
+                let _ = write!(out, "{:?}", value);
+            }
+            _ => out.push_str("null"),
+        },
+        _ => out.push_str("null"),
+    }
+}
+
+/// Appends `text` as a quoted JSON string.
+fn push_str(out: &mut String, text: &str) {
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ if (ch as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out.push('"');
+}
@@ -1,11 +1,39 @@
 #[cfg(test)]
 mod tests;
 
+mod binary;
+mod config;
+mod diagnostic;
+mod error;
+mod input;
+mod json;
+mod literal;
+mod span;
+mod stream;
+mod trivia;
+
+pub use binary::{TokenReader, TokenWriter};
+pub use config::{BlockComment, CharClass, LexerConfig, Newlines, StringDelimiter};
+pub use diagnostic::{Diagnostic, Label, Level, Renderer};
+pub use error::{FileError, LexError};
+pub use json::JsonWriter;
+pub use literal::{IntValue, Literal};
+pub use span::{LineCol, SourceMap, Span};
+pub use stream::StreamLexer;
+pub use trivia::{TriviaLexer, TriviaToken};
+
 use core::fmt;
 use std::{
-    env::Args, fs::File, io::{self, Read}, path::PathBuf
+    borrow::Cow,
+    env::Args,
+    fs::File,
+    io::{self, Read},
+    iter::FusedIterator,
+    path::{Path, PathBuf},
 };
 
+use input::Input;
+
 pub fn read_file_to_bytes(filepath: PathBuf) -> io::Result<Vec<u8>> {
     let mut file = File::open(filepath)?;
     let mut data = vec![];
@@ -14,53 +42,156 @@ pub fn read_file_to_bytes(filepath: PathBuf) -> io::Result<Vec<u8>> {
 }
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TokenKind {
     Whitespace,
+    Newline,
     Invalid,
     Null,
     Int,
     Float,
     Identifier,
+    Keyword(usize),
     Ponct,
     Op,
+    String,
+    Char,
+    Comment,
+    DocComment,
+}
+
+impl TokenKind {
+    /// Every value [`TokenKind::name`] returns.
+    pub const NAMES: [&'static str; 14] = [
+        "Whitespace",
+        "Newline",
+        "Invalid",
+        "Null",
+        "Int",
+        "Float",
+        "Identifier",
+        "Keyword",
+        "Ponct",
+        "Op",
+        "String",
+        "Char",
+        "Comment",
+        "DocComment",
+    ];
+
+    /// Name of the variant, without the id of a `Keyword`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::Whitespace => "Whitespace",
+            TokenKind::Newline => "Newline",
+            TokenKind::Invalid => "Invalid",
+            TokenKind::Null => "Null",
+            TokenKind::Int => "Int",
+            TokenKind::Float => "Float",
+            TokenKind::Identifier => "Identifier",
+            TokenKind::Keyword(_) => "Keyword",
+            TokenKind::Ponct => "Ponct",
+            TokenKind::Op => "Op",
+            TokenKind::String => "String",
+            TokenKind::Char => "Char",
+            TokenKind::Comment => "Comment",
+            TokenKind::DocComment => "DocComment",
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
-pub struct Token {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token<'a> {
     pub kind: TokenKind,
-    pub value: String,
-    pub loc: (usize, usize),
+    pub value: Cow<'a, str>,
+    pub span: Span,
+    pub literal: Option<Literal<'a>>,
+    /// Type suffix of a number literal, such as `u32` in `10u32`.
+    pub suffix: Option<Cow<'a, str>>,
 }
 
-impl fmt::Display for Token {
+impl fmt::Display for Token<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}:{} {:?} -> {}",
-            self.loc.0, self.loc.1, self.kind, self.value
-        )
+        write!(f, "{} {:?} -> {}", self.span, self.kind, self.value)
     }
 }
 
-impl Token {
-    fn new(kind: TokenKind, value: String, loc: (usize, usize)) -> Self {
-        Self { kind, value, loc }
+impl<'a> Token<'a> {
+    fn new(kind: TokenKind, value: Cow<'a, str>, span: Span) -> Self {
+        Self {
+            kind,
+            value,
+            span,
+            literal: None,
+            suffix: None,
+        }
     }
 
-    fn invalid(value: String, loc: (usize, usize)) -> Self {
-        Self::new(TokenKind::Invalid, value, loc)
+    fn with_literal(mut self, literal: Literal<'a>) -> Self {
+        self.literal = Some(literal);
+        self
     }
 
-    fn null(loc: (usize, usize)) -> Self {
-        Self::new(TokenKind::Null, String::new(), loc)
+    pub fn empty() -> Self {
+        Self::new(TokenKind::Null, Cow::Borrowed(""), Span::default())
     }
 
-    pub fn empty() -> Self {
-        Self::new(TokenKind::Null, String::new(), (0, 0))
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            kind: self.kind,
+            value: Cow::Owned(self.value.into_owned()),
+            span: self.span,
+            literal: self.literal.map(Literal::into_owned),
+            suffix: self.suffix.map(|suffix| Cow::Owned(suffix.into_owned())),
+        }
+    }
+
+    /// Radix and digits of an `Int` token.
+    pub fn int_value(&self) -> Option<IntValue<'_>> {
+        match self.kind {
+            TokenKind::Int => Some(literal::int_value(self.number_text(), self.span)),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<Result<u64, LexError>> {
+        self.int_value().map(|value| value.to_u64())
+    }
+
+    pub fn as_i64(&self) -> Option<Result<i64, LexError>> {
+        self.int_value().map(|value| value.to_i64())
+    }
+
+    pub fn as_i128(&self) -> Option<Result<i128, LexError>> {
+        self.int_value().map(|value| value.to_i128())
+    }
+
+    /// Value of a `Float` or `Int` token.
+    pub fn as_f64(&self) -> Option<Result<f64, LexError>> {
+        match self.kind {
+            TokenKind::Float => Some(literal::float_value(self.number_text(), self.span)),
+            TokenKind::Int => self.int_value().map(|value| value.to_f64()),
+            _ => None,
+        }
+    }
+
+    fn number_text(&self) -> &str {
+        let suffix = self.suffix.as_ref().map_or(0, |suffix| suffix.len());
+        &self.value[..self.value.len() - suffix]
+    }
+
+    /// Maps a `Keyword` token to a user type, typically an enum whose
+    /// discriminants follow the order the keywords were registered in.
+    pub fn keyword<K: TryFrom<usize>>(&self) -> Option<K> {
+        match self.kind {
+            TokenKind::Keyword(id) => K::try_from(id).ok(),
+            _ => None,
+        }
     }
 
     pub fn fmt_loc(&self) -> String {
-        format!("{}:{}", self.loc.0, self.loc.1)
+        format!("{}", self.span)
     }
 
     pub fn fmt_kind(&self) -> String {
@@ -68,31 +199,63 @@ impl Token {
     }
 
     pub fn fmt_value(&self) -> String {
-        format!("{}", self.value)
+        self.value.to_string()
     }
 }
 
-pub struct Lexer {
-    input: Vec<u8>,
+/// Lexes a borrowed, owned or memory-mapped input. Tokens of a borrowed
+/// input slice it without copying; other tokens own their text.
+pub struct Lexer<'a> {
+    input: Input<'a>,
+    config: LexerConfig,
 
     max_position: usize,
 
     position: usize,
-    col: usize,
-    row: usize,
+    start: usize,
+    malformed: bool,
+    finished: bool,
+
+    diagnostics: Vec<LexError>,
 }
 
-impl Lexer {
-    pub fn new(input: Vec<u8>) -> Self {
+impl<'a> Lexer<'a> {
+    pub fn new(input: impl Into<Cow<'a, [u8]>>) -> Self {
+        Self::with_config(input, LexerConfig::default())
+    }
+
+    pub fn with_config(input: impl Into<Cow<'a, [u8]>>, config: LexerConfig) -> Self {
+        Self::from_input(Input::from(input.into()), config)
+    }
+
+    fn from_input(input: Input<'a>, config: LexerConfig) -> Self {
         let max = input.len();
         Self {
             input,
+            config,
             max_position: max,
             position: 0,
-            col: 1,
-            row: 1,
+            start: 0,
+            malformed: false,
+            finished: false,
+            diagnostics: vec![],
         }
     }
+
+    /// Lexes the file at `path`, memory-mapped when it is a regular file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Lexer<'static>, FileError> {
+        Lexer::from_path_with_config(path, LexerConfig::default())
+    }
+
+    pub fn from_path_with_config(
+        path: impl AsRef<Path>,
+        config: LexerConfig,
+    ) -> Result<Lexer<'static>, FileError> {
+        let path = path.as_ref();
+        let input = Input::open(path).map_err(|err| FileError::new(path, err))?;
+        Ok(Lexer::from_input(input, config))
+    }
+
     pub fn from_args(args: Args) -> Self {
         if let Some(s) = args.reduce(|acc, a| format!("{} {}", acc, a)) {
             Self::new(s.into_bytes())
@@ -101,6 +264,43 @@ impl Lexer {
         }
     }
 
+    pub fn source_map(&self) -> SourceMap<'_> {
+        SourceMap::new(&self.input)
+    }
+
+    /// Errors found so far, in input order.
+    pub fn diagnostics(&self) -> &[LexError] {
+        &self.diagnostics
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<LexError> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    pub(crate) fn seek(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    /// Whether only whitespace and line breaks follow `position`, so that a
+    /// merged `Newline` ending there could grow with more input.
+    pub(crate) fn blank_after(&self, mut position: usize) -> bool {
+        while position < self.max_position {
+            match literal::decode_utf8(&self.input[position..]) {
+                Ok((ch, len)) if (self.config.whitespace)(ch) || ch == '\n' || ch == '\r' => {
+                    position += len
+                }
+                Ok(_) => return false,
+                // A character cut off by the end of the input may be blank.
+                Err(_) => return self.max_position - position < 4,
+            }
+        }
+        true
+    }
+
+    pub(crate) fn into_parts(self) -> (LexerConfig, Vec<LexError>) {
+        (self.config, self.diagnostics)
+    }
+
     fn current_byte(&self) -> u8 {
         if self.has_next() {
             self.input[self.position]
@@ -109,6 +309,14 @@ impl Lexer {
         }
     }
 
+    fn decode(&self) -> Result<(char, usize), usize> {
+        literal::decode_utf8(self.rest())
+    }
+
+    fn current_char(&self) -> Option<char> {
+        self.decode().ok().map(|(ch, _)| ch)
+    }
+
     fn next_byte(&self) -> u8 {
         self.peek(1)
     }
@@ -123,109 +331,546 @@ impl Lexer {
         }
     }
 
+    fn rest(&self) -> &[u8] {
+        &self.input[self.position..]
+    }
+
     fn advance_char(&mut self) {
-        self.position += 1;
-        self.col += 1;
+        if !self.has_next() {
+            return;
+        }
+        let (Ok((_, len)) | Err(len)) = self.decode();
+        self.position += len.max(1);
+    }
+
+    fn advance_bytes(&mut self, len: usize) {
+        let stop = (self.position + len).min(self.max_position);
+        while self.position < stop {
+            self.advance_char();
+        }
     }
 
     fn has_next(&self) -> bool {
         self.position < self.max_position
     }
 
-    pub fn next(&mut self) -> Token {
-        match self.current_byte() {
-            ch if ch.is_ascii_alphabetic() || ch == b'_' => {
-                self.identifier(self.position)
-            }
+    fn scan(&mut self) -> Token<'a> {
+        self.start = self.position;
+        self.malformed = false;
+
+        if !self.has_next() {
+            return self.null();
+        }
+
+        if let Some(comment) = self.match_block_comment() {
+            return self.block_comment(comment);
+        }
+        if let Some(kind) = self.match_line_comment() {
+            return self.line_comment(kind);
+        }
+
+        let ch = match self.decode() {
+            Ok((ch, _)) => ch,
+            Err(_) => return self.unexpected(),
+        };
+
+        match ch {
+            ch if (self.config.ident_start)(ch) => self.identifier(),
             ch if ch.is_ascii_digit() => self.number(),
-            ch if ch.is_ascii_punctuation() => {
-                self.position += 1;
-                Token::new(
-                    TokenKind::Ponct,
-                    format!("{}", ch as char),
-                    (self.row, self.col),
-                )
-            }
-            ch if ch.is_ascii_whitespace() => self.whitespace(),
-            _ => {
-                if self.has_next() {
-                    self.invalid(self.position, self.position + 1)
-                } else {
-                    self.null()
+            '.' if self.leading_dot_float() => self.number(),
+            _ if self.newline_len() > 0 => self.newline(),
+            ch if (self.config.whitespace)(ch) => self.whitespace(),
+            ch if self.string_delimiter(ch).is_some() => self.string(ch),
+            ch if self.config.char_quote == Some(ch) => self.char_literal(ch),
+            _ if self.match_operator().is_some() => self.operator(),
+            ch if (self.config.operator)(ch) => self.single(TokenKind::Op),
+            ch if ch.is_ascii_punctuation() => self.single(TokenKind::Ponct),
+            _ => self.unexpected(),
+        }
+    }
+
+    /// A `.` followed by a digit starts a float, unless it ends a `..`.
+    fn leading_dot_float(&self) -> bool {
+        self.config.leading_dot_floats
+            && self.next_byte().is_ascii_digit()
+            && (self.position == 0 || self.input[self.position - 1] != b'.')
+    }
+
+    fn starts_token(&self) -> bool {
+        if self.match_block_comment().is_some()
+            || self.match_line_comment().is_some()
+            || self.match_operator().is_some()
+            || self.newline_len() > 0
+        {
+            return true;
+        }
+
+        self.current_char().is_some_and(|ch| {
+            (self.config.ident_start)(ch)
+                || ch.is_ascii_digit()
+                || (self.config.whitespace)(ch)
+                || self.string_delimiter(ch).is_some()
+                || self.config.char_quote == Some(ch)
+                || (self.config.operator)(ch)
+                || ch.is_ascii_punctuation()
+        })
+    }
+
+    /// Lexes input that cannot start a token. When recovering, the `Invalid`
+    /// token extends over the whole run of such input.
+    fn unexpected(&mut self) -> Token<'a> {
+        loop {
+            let start = self.position;
+            match self.decode() {
+                Ok(_) => {
+                    self.advance_char();
+                    self.error(LexError::UnexpectedChar(self.span_from(start)));
                 }
+                Err(len) => {
+                    self.advance_bytes(len);
+                    self.error(LexError::InvalidUtf8(self.span_from(start)));
+                }
+            }
+
+            if !self.config.recover || !self.has_next() || self.starts_token() {
+                break;
             }
         }
+
+        self.invalid(self.position)
     }
 
-    fn whitespace(&mut self) -> Token {
+    /// Skips `len` malformed bytes inside the current token, reporting them as
+    /// `error`. Without recovery the token ends there as `Invalid`.
+    fn malformed(&mut self, error: fn(Span) -> LexError, len: usize) -> Option<Token<'a>> {
         let start = self.position;
+        self.advance_bytes(len);
+        self.reject(error(self.span_from(start)))
+    }
 
-        while self.has_next() {
-            match self.current_byte() {
-                b' ' | b'\t' | b'\r' => self.advance_char(),
-                b'\n' => {
-                    self.row += 1;
-                    self.advance_char();
-                }
-                _ => break,
+    fn reject(&mut self, error: LexError) -> Option<Token<'a>> {
+        self.error(error);
+
+        if self.config.recover {
+            self.malformed = true;
+            None
+        } else {
+            Some(self.invalid(self.position))
+        }
+    }
+
+    fn unterminated(&mut self, error: fn(Span) -> LexError) -> Token<'a> {
+        self.error(error(self.span_from(self.start)));
+        self.invalid(self.position)
+    }
+
+    fn error(&mut self, error: LexError) {
+        match (self.diagnostics.last_mut(), &error) {
+            (Some(LexError::UnexpectedChar(last)), LexError::UnexpectedChar(span))
+                if last.end == span.start =>
+            {
+                last.end = span.end;
             }
+            _ => self.diagnostics.push(error),
         }
+    }
 
-        let value = self.slice_string(start, self.position);
+    fn span_from(&self, start: usize) -> Span {
+        Span::new(start, self.position)
+    }
 
-        Token::new(TokenKind::Whitespace, value, (self.row, self.col))
+    fn whitespace(&mut self) -> Token<'a> {
+        self.skip_whitespace();
+        self.token(TokenKind::Whitespace)
     }
 
-    fn number(&mut self) -> Token {
-        let start = self.position;
+    fn skip_whitespace(&mut self) {
+        while self.newline_len() == 0
+            && self
+                .current_char()
+                .is_some_and(|ch| (self.config.whitespace)(ch))
+        {
+            self.advance_char();
+        }
+    }
 
+    /// Length of the line break at the position, or 0 if there is none or
+    /// line breaks are plain whitespace.
+    fn newline_len(&self) -> usize {
+        match (self.config.newlines, self.current_byte(), self.next_byte()) {
+            (Newlines::Whitespace, _, _) => 0,
+            (_, b'\n', _) => 1,
+            (_, b'\r', b'\n') => 2,
+            _ => 0,
+        }
+    }
+
+    fn newline(&mut self) -> Token<'a> {
+        self.advance_bytes(self.newline_len());
+
+        if self.config.newlines == Newlines::Merge {
+            loop {
+                let line_start = self.position;
+                self.skip_whitespace();
+                match self.newline_len() {
+                    0 => {
+                        self.position = line_start;
+                        break;
+                    }
+                    len => self.advance_bytes(len),
+                }
+            }
+        }
+
+        self.token(TokenKind::Newline)
+    }
+
+    fn number(&mut self) -> Token<'a> {
+        match self.scan_number() {
+            Ok(tok) | Err(tok) => tok,
+        }
+    }
+
+    /// Lexes a number literal. `Err` holds the `Invalid` token ending the
+    /// input when a malformed literal is found without recovery.
+    fn scan_number(&mut self) -> Result<Token<'a>, Token<'a>> {
         let mut kind = TokenKind::Int;
 
+        let radix = match (self.current_byte(), self.next_byte()) {
+            (b'0', b'x' | b'X') => 16,
+            (b'0', b'o' | b'O') => 8,
+            (b'0', b'b' | b'B') => 2,
+            _ => 10,
+        };
+
+        if radix != 10 {
+            self.advance_bytes(2);
+            // A rejected digit already explains why no valid digit follows.
+            if self.digits(radix)? == 0 && !self.malformed {
+                let error = LexError::MissingDigits(self.span_from(self.start));
+                if let Some(tok) = self.reject(error) {
+                    return Err(tok);
+                }
+            }
+        } else {
+            self.digits(10)?;
+
+            if self.current_byte() == b'.' && self.next_byte().is_ascii_digit() {
+                kind = TokenKind::Float;
+                self.advance_char();
+                self.digits(10)?;
+            }
+
+            if matches!(self.current_byte(), b'e' | b'E') {
+                let signed = matches!(self.next_byte(), b'+' | b'-');
+                if self.peek(1 + signed as usize).is_ascii_digit() {
+                    kind = TokenKind::Float;
+                    self.advance_bytes(1 + signed as usize);
+                    self.digits(10)?;
+                } else if let Some(tok) =
+                    self.malformed(LexError::MalformedExponent, 1 + signed as usize)
+                {
+                    return Err(tok);
+                }
+            }
+        }
+
+        let suffix = self.position;
+        if self
+            .current_char()
+            .is_some_and(|ch| (self.config.ident_start)(ch))
+        {
+            self.advance_char();
+            while self
+                .current_char()
+                .is_some_and(|ch| (self.config.ident_continue)(ch))
+            {
+                self.advance_char();
+            }
+        }
+
+        let mut tok = self.token(kind);
+        if self.position > suffix {
+            tok.suffix = Some(self.text(suffix, self.position));
+        }
+        Ok(tok)
+    }
+
+    /// Consumes digits and `_` separators, returning the number of digits.
+    fn digits(&mut self, radix: u32) -> Result<usize, Token<'a>> {
+        let mut count = 0;
+
         loop {
             match self.current_byte() {
-                b'0'..=b'9' => {}
-                b'.' if (b'0'..=b'9').contains(&self.next_byte()) => {
-                    kind = TokenKind::Float;
+                b'_' => self.advance_char(),
+                b if (b as char).is_digit(radix) => {
+                    count += 1;
+                    self.advance_char();
+                }
+                b if b.is_ascii_digit() => {
+                    if let Some(tok) = self.malformed(LexError::InvalidDigit, 1) {
+                        return Err(tok);
+                    }
+                }
+                _ => return Ok(count),
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> Token<'a> {
+        self.advance_char();
+        while self
+            .current_char()
+            .is_some_and(|ch| (self.config.ident_continue)(ch))
+        {
+            self.advance_char();
+        }
+
+        let mut tok = self.token(TokenKind::Identifier);
+        if let Some(&id) = self.config.keywords.get(tok.value.as_ref()) {
+            tok.kind = TokenKind::Keyword(id);
+        }
+        tok
+    }
+
+    fn match_line_comment(&self) -> Option<TokenKind> {
+        let rest = self.rest();
+        let starts = |prefixes: &[String]| {
+            prefixes
+                .iter()
+                .any(|prefix| !prefix.is_empty() && rest.starts_with(prefix.as_bytes()))
+        };
+
+        if starts(&self.config.doc_comments) {
+            Some(TokenKind::DocComment)
+        } else if starts(&self.config.line_comments) {
+            Some(TokenKind::Comment)
+        } else {
+            None
+        }
+    }
+
+    fn line_comment(&mut self, kind: TokenKind) -> Token<'a> {
+        while self.has_next() && self.newline_len() == 0 {
+            match self.decode() {
+                Ok(('\n', _)) => break,
+                Ok(_) => self.advance_char(),
+                Err(len) => {
+                    if let Some(tok) = self.malformed(LexError::InvalidUtf8, len) {
+                        return tok;
+                    }
                 }
-                _ => break,
             }
+        }
+
+        self.token(kind)
+    }
+
+    fn match_block_comment(&self) -> Option<BlockComment> {
+        let rest = self.rest();
+        self.config
+            .block_comments
+            .iter()
+            .find(|c| !c.open.is_empty() && rest.starts_with(c.open.as_bytes()))
+            .cloned()
+    }
+
+    fn block_comment(&mut self, comment: BlockComment) -> Token<'a> {
+        let mut depth = 1;
+
+        self.advance_bytes(comment.open.len());
 
-            self.position += 1;
+        while depth > 0 {
+            if !self.has_next() {
+                return self.unterminated(LexError::UnterminatedComment);
+            }
+
+            let rest = self.rest();
+            if rest.starts_with(comment.close.as_bytes()) {
+                depth -= 1;
+                self.advance_bytes(comment.close.len());
+            } else if comment.nested && rest.starts_with(comment.open.as_bytes()) {
+                depth += 1;
+                self.advance_bytes(comment.open.len());
+            } else if let Err(len) = self.decode() {
+                if let Some(tok) = self.malformed(LexError::InvalidUtf8, len) {
+                    return tok;
+                }
+            } else {
+                self.advance_char();
+            }
         }
 
-        self.token(kind, start)
+        self.token(TokenKind::Comment)
+    }
+
+    fn string_delimiter(&self, ch: char) -> Option<StringDelimiter> {
+        self.config.strings.iter().find(|d| d.quote == ch).copied()
     }
 
-    fn identifier(&mut self, start: usize) -> Token {
+    fn string(&mut self, quote: char) -> Token<'a> {
+        let raw = self.string_delimiter(quote).is_some_and(|d| d.raw);
+        let mut decoded: Option<String> = None;
+
+        self.advance_char();
+
+        let content = self.position;
+
         loop {
-            let ch = self.current_byte();
-            if !(ch.is_ascii_alphabetic() || ch == b'_') {
-                break;
+            match self.decode() {
+                _ if !self.has_next() => {
+                    return self.unterminated(LexError::UnterminatedString);
+                }
+                Ok((ch, _)) if ch == quote => break,
+                Ok(('\\', _)) if !raw => {
+                    let Some((ch, len)) = literal::unescape(&self.rest()[1..], quote) else {
+                        let (Ok((_, len)) | Err(len)) = literal::decode_utf8(&self.rest()[1..]);
+                        if let Some(tok) = self.malformed(LexError::InvalidEscape, 1 + len) {
+                            return tok;
+                        }
+                        continue;
+                    };
+                    decoded
+                        .get_or_insert_with(|| self.text(content, self.position).into_owned())
+                        .push(ch);
+                    self.advance_bytes(1 + len);
+                }
+                Ok((ch, _)) => {
+                    if let Some(decoded) = &mut decoded {
+                        decoded.push(ch);
+                    }
+                    self.advance_char();
+                }
+                Err(len) => {
+                    if let Some(tok) = self.malformed(LexError::InvalidUtf8, len) {
+                        return tok;
+                    }
+                }
+            }
+        }
+
+        let decoded = match decoded {
+            Some(decoded) => Cow::Owned(decoded),
+            None => self.text(content, self.position),
+        };
+
+        self.advance_char();
+
+        self.token(TokenKind::String)
+            .with_literal(Literal::Str(decoded))
+    }
+
+    fn char_literal(&mut self, quote: char) -> Token<'a> {
+        let start = self.position;
+        let body = start + quote.len_utf8();
+        let rest = &self.input[body..];
+        let mut buf = [0; 4];
+        let quote_bytes = quote.encode_utf8(&mut buf).as_bytes();
+
+        let decoded = match literal::decode_utf8(rest) {
+            Ok(('\\', _)) => literal::unescape(&rest[1..], quote).map(|(ch, len)| (ch, len + 1)),
+            Ok((ch, _)) if ch == quote => None,
+            Ok(decoded) => Some(decoded),
+            Err(_) => None,
+        };
+
+        match decoded {
+            Some((ch, len)) if rest[len..].starts_with(quote_bytes) => {
+                self.advance_bytes(len + 2 * quote_bytes.len());
+                self.token(TokenKind::Char).with_literal(Literal::Char(ch))
+            }
+            _ if rest.starts_with(quote_bytes) => {
+                let stop = body + quote_bytes.len();
+                self.error(LexError::InvalidChar(Span::new(start, stop)));
+                self.invalid(stop)
             }
-            self.position += 1
+            _ if rest.first() == Some(&b'\\') => {
+                let (Ok((_, len)) | Err(len)) = literal::decode_utf8(&rest[1..]);
+                let mut stop = (body + 1 + len).min(self.max_position);
+                self.error(LexError::InvalidEscape(Span::new(body, stop)));
+                if self.input[stop..].starts_with(quote_bytes) {
+                    stop += quote_bytes.len();
+                }
+                self.invalid(stop)
+            }
+            _ => self.single(TokenKind::Ponct),
         }
+    }
 
-        self.token(TokenKind::Identifier, start)
+    fn single(&mut self, kind: TokenKind) -> Token<'a> {
+        self.advance_char();
+        self.token(kind)
     }
 
-    fn token(&mut self, kind: TokenKind, start: usize) -> Token {
-        let value = self.slice_string(start, self.position);
-        Token::new(kind, value, (self.row, self.col))
+    fn match_operator(&self) -> Option<usize> {
+        let rest = self.rest();
+        self.config
+            .operators
+            .iter()
+            .find(|op| rest.starts_with(op.as_bytes()))
+            .map(String::len)
     }
 
-    fn slice_string(&mut self, start: usize, stop: usize) -> String {
-        String::from_utf8_lossy(&self.input[start..stop]).into_owned()
+    fn operator(&mut self) -> Token<'a> {
+        let len = self.match_operator().unwrap_or(1);
+        self.advance_bytes(len);
+        self.token(TokenKind::Op)
     }
 
-    fn invalid(&mut self, start: usize, stop: usize) -> Token {
-        let value = self.slice_string(start, stop);
+    fn token(&mut self, kind: TokenKind) -> Token<'a> {
+        let kind = if self.malformed {
+            TokenKind::Invalid
+        } else {
+            kind
+        };
+        let value = self.text(self.start, self.position);
+        Token::new(kind, value, Span::new(self.start, self.position))
+    }
 
-        self.position = self.max_position;
+    fn text(&self, start: usize, stop: usize) -> Cow<'a, str> {
+        match self.input {
+            Input::Borrowed(input) => String::from_utf8_lossy(&input[start..stop]),
+            _ => Cow::Owned(String::from_utf8_lossy(&self.input[start..stop]).into_owned()),
+        }
+    }
 
-        Token::invalid(value, (self.row, self.col))
+    fn invalid(&mut self, stop: usize) -> Token<'a> {
+        self.advance_bytes(stop - self.position);
+
+        let tok = self.token(TokenKind::Invalid);
+
+        if !self.config.recover {
+            self.position = self.max_position;
+        }
+
+        tok
+    }
+
+    fn null(&mut self) -> Token<'a> {
+        self.token(TokenKind::Null)
     }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        while !self.finished {
+            let tok = self.scan();
+            match tok.kind {
+                TokenKind::Null => {
+                    self.finished = true;
+                    if self.config.emit_eof {
+                        return Some(tok);
+                    }
+                }
+                TokenKind::Comment | TokenKind::DocComment if !self.config.emit_comments => {}
+                _ => return Some(tok),
+            }
+        }
 
-    fn null(&self) -> Token {
-        Token::null((self.row, self.col))
+        None
     }
 }
+
+impl FusedIterator for Lexer<'_> {}
@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+
+use crate::{LexError, Span};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Literal<'a> {
+    Str(Cow<'a, str>),
+    Char(char),
+}
+
+impl Literal<'_> {
+    pub fn into_owned(self) -> Literal<'static> {
+        match self {
+            Literal::Str(s) => Literal::Str(Cow::Owned(s.into_owned())),
+            Literal::Char(ch) => Literal::Char(ch),
+        }
+    }
+}
+
+/// Digits of an integer literal without prefix, separators or suffix. The
+/// digits are kept as text so values wider than `u128` can still be read.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct IntValue<'t> {
+    pub radix: u32,
+    pub digits: Cow<'t, str>,
+    pub span: Span,
+}
+
+impl IntValue<'_> {
+    pub fn to_u128(&self) -> Result<u128, LexError> {
+        u128::from_str_radix(&self.digits, self.radix)
+            .map_err(|_| LexError::NumberOverflow(self.span))
+    }
+
+    pub fn to_i128(&self) -> Result<i128, LexError> {
+        self.narrow()
+    }
+
+    pub fn to_u64(&self) -> Result<u64, LexError> {
+        self.narrow()
+    }
+
+    pub fn to_i64(&self) -> Result<i64, LexError> {
+        self.narrow()
+    }
+
+    /// Nearest `f64` to the value. Non-decimal values wider than `u128`
+    /// are only approximated.
+    pub fn to_f64(&self) -> Result<f64, LexError> {
+        let value = match (self.radix, self.to_u128()) {
+            (10, _) => self
+                .digits
+                .parse()
+                .map_err(|_| LexError::NumberOverflow(self.span))?,
+            (_, Ok(value)) => value as f64,
+            (radix, Err(_)) => self.digits.chars().fold(0.0, |acc, digit| {
+                acc * radix as f64 + digit.to_digit(radix).unwrap_or(0) as f64
+            }),
+        };
+        finite(value, self.span)
+    }
+
+    fn narrow<T: TryFrom<u128>>(&self) -> Result<T, LexError> {
+        T::try_from(self.to_u128()?).map_err(|_| LexError::NumberOverflow(self.span))
+    }
+}
+
+/// Splits the text of an `Int` token into its radix and digits.
+pub(crate) fn int_value<'t>(text: &'t str, span: Span) -> IntValue<'t> {
+    let (radix, digits) = match text.get(..2) {
+        Some("0x" | "0X") => (16, &text[2..]),
+        Some("0o" | "0O") => (8, &text[2..]),
+        Some("0b" | "0B") => (2, &text[2..]),
+        _ => (10, text),
+    };
+
+    IntValue {
+        radix,
+        digits: strip_separators(digits),
+        span,
+    }
+}
+
+/// Parses the text of a `Float` token.
+pub(crate) fn float_value(text: &str, span: Span) -> Result<f64, LexError> {
+    let value = strip_separators(text)
+        .parse()
+        .map_err(|_| LexError::NumberOverflow(span))?;
+    finite(value, span)
+}
+
+fn finite(value: f64, span: Span) -> Result<f64, LexError> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(LexError::NumberOverflow(span))
+    }
+}
+
+fn strip_separators(digits: &str) -> Cow<'_, str> {
+    if digits.contains('_') {
+        Cow::Owned(digits.replace('_', ""))
+    } else {
+        Cow::Borrowed(digits)
+    }
+}
+
+/// Decodes the UTF-8 character at the start of `input`, returning it and its
+/// width in bytes, or the length of the invalid sequence found instead.
+pub(crate) fn decode_utf8(input: &[u8]) -> Result<(char, usize), usize> {
+    let head = &input[..input.len().min(4)];
+    let valid = match std::str::from_utf8(head) {
+        Ok(valid) => valid,
+        Err(err) if err.valid_up_to() > 0 => {
+            std::str::from_utf8(&head[..err.valid_up_to()]).unwrap_or_default()
+        }
+        Err(err) => return Err(err.error_len().unwrap_or(head.len())),
+    };
+
+    valid.chars().next().map(|ch| (ch, ch.len_utf8())).ok_or(0)
+}
+
+/// Decodes the escape sequence following a backslash, returning the
+/// character and the number of bytes it spans.
+pub(crate) fn unescape(input: &[u8], quote: char) -> Option<(char, usize)> {
+    let ch = match *input.first()? {
+        b'n' => '\n',
+        b't' => '\t',
+        b'r' => '\r',
+        b'0' => '\0',
+        b'\\' => '\\',
+        b'"' => '"',
+        b'\'' => '\'',
+        b'x' => {
+            let value = hex_value(input.get(1..3)?)?;
+            return Some((char::from_u32(value)?, 3));
+        }
+        b'u' => {
+            if input.get(1) != Some(&b'{') {
+                return None;
+            }
+            let close = input.iter().position(|&b| b == b'}')?;
+            let digits = &input[2..close];
+            if digits.is_empty() || digits.len() > 6 {
+                return None;
+            }
+            let value = hex_value(digits)?;
+            return Some((char::from_u32(value)?, close + 1));
+        }
+        _ => {
+            return decode_utf8(input).ok().filter(|&(ch, _)| ch == quote);
+        }
+    };
+
+    Some((ch, 1))
+}
+
+/// Value of hexadecimal `digits`, rejecting the sign `from_str_radix` allows.
+fn hex_value(digits: &[u8]) -> Option<u32> {
+    if !digits.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    u32::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()
+}
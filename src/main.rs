@@ -0,0 +1,248 @@
+use std::{
+    env,
+    io::{self, BufWriter, IsTerminal, Read, Write},
+    process::ExitCode,
+};
+
+use mlexer::{Diagnostic, JsonWriter, Lexer, LexerConfig, Renderer, SourceMap, Token, TokenKind};
+
+const USAGE: &str = "\
+Usage: mlexer [OPTIONS] [FILE]...
+
+Prints the tokens of each FILE, or of standard input when no FILE is given
+or FILE is `-`, as `row:col Kind -> value`.
+
+Options:
+  -w, --no-whitespace   hide whitespace tokens
+  -k, --kind KIND       only print tokens of KIND (repeatable, e.g. `-k Identifier`)
+  -d, --deny-invalid    exit with status 1 if any Invalid token is found
+  -f, --format FORMAT   output `text` (default), a `json` array or `jsonl` lines
+  -h, --help            print this help";
+
+#[derive(Default, PartialEq, Debug)]
+enum Format {
+    #[default]
+    Text,
+    Json,
+    JsonLines,
+}
+
+/// Command-line options of the `mlexer` binary.
+#[derive(Default)]
+struct Options {
+    format: Format,
+    no_whitespace: bool,
+    kinds: Vec<String>,
+    deny_invalid: bool,
+    help: bool,
+    files: Vec<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-w" | "--no-whitespace" => options.no_whitespace = true,
+                "-d" | "--deny-invalid" => options.deny_invalid = true,
+                "-h" | "--help" => options.help = true,
+                "-k" | "--kind" => match args.next() {
+                    Some(kind) => {
+                        let name = kind.to_lowercase();
+                        if !TokenKind::NAMES
+                            .iter()
+                            .any(|known| known.to_lowercase() == name)
+                        {
+                            return Err(format!("unknown token kind `{}`", kind));
+                        }
+                        options.kinds.push(name);
+                    }
+                    None => return Err(format!("missing KIND after `{}`", arg)),
+                },
+                "-f" | "--format" => {
+                    options.format = match args.next().as_deref() {
+                        Some("text") => Format::Text,
+                        Some("json") => Format::Json,
+                        Some("jsonl") => Format::JsonLines,
+                        Some(format) => return Err(format!("unknown format `{}`", format)),
+                        None => return Err(format!("missing FORMAT after `{}`", arg)),
+                    }
+                }
+                "--" => options.files.extend(args.by_ref()),
+                _ if arg.starts_with('-') && arg != "-" => {
+                    return Err(format!("unknown option `{}`", arg))
+                }
+                _ => options.files.push(arg),
+            }
+        }
+
+        if options.files.is_empty() {
+            options.files.push("-".to_string());
+        }
+        Ok(options)
+    }
+
+    fn shows(&self, kind: TokenKind) -> bool {
+        if self.no_whitespace && kind == TokenKind::Whitespace {
+            return false;
+        }
+        self.kinds.is_empty() || self.kinds.contains(&kind.name().to_lowercase())
+    }
+}
+
+enum Printer<W: Write> {
+    Text(W),
+    Json(JsonWriter<W>),
+}
+
+impl<W: Write> Printer<W> {
+    fn new(format: &Format, out: W) -> Self {
+        match format {
+            Format::Text => Printer::Text(out),
+            Format::Json => Printer::Json(JsonWriter::new(out)),
+            Format::JsonLines => Printer::Json(JsonWriter::lines(out)),
+        }
+    }
+
+    /// Names the file the next tokens come from, when there are several.
+    fn set_origin(&mut self, origin: Option<&str>) {
+        if let Printer::Json(json) = self {
+            json.set_origin(origin.map(str::to_string));
+        }
+    }
+
+    fn print(
+        &mut self,
+        origin: Option<&str>,
+        tok: &Token<'_>,
+        map: &SourceMap<'_>,
+    ) -> io::Result<()> {
+        match self {
+            Printer::Text(out) => match origin {
+                Some(origin) => writeln!(out, "{}:{}", origin, map.display(tok)),
+                None => writeln!(out, "{}", map.display(tok)),
+            },
+            Printer::Json(json) => json.write(tok, map),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Printer::Text(mut out) => out.flush(),
+            Printer::Json(json) => json.finish().map(drop),
+        }
+    }
+}
+
+fn open(file: &str) -> io::Result<Lexer<'static>> {
+    let config = LexerConfig::new().emit_eof(false);
+    if file == "-" {
+        let mut data = vec![];
+        io::stdin().read_to_end(&mut data)?;
+        return Ok(Lexer::with_config(data, config));
+    }
+    Ok(Lexer::from_path_with_config(file, config)?)
+}
+
+/// Prints the tokens of `file`, returning whether an Invalid token was found.
+fn dump(file: &str, options: &Options, printer: &mut Printer<impl Write>) -> io::Result<bool> {
+    let mut lexer = open(file)?;
+    let tokens: Vec<_> = lexer.by_ref().collect();
+    let map = lexer.source_map();
+    let name = if file == "-" { "<stdin>" } else { file };
+    let origin = (options.files.len() > 1).then_some(name);
+
+    printer.set_origin(origin);
+    for tok in tokens.iter().filter(|tok| options.shows(tok.kind)) {
+        printer.print(origin, tok, &map)?;
+    }
+
+    let renderer = Renderer::new()
+        .colors(io::stderr().is_terminal())
+        .origin(name);
+    for err in lexer.diagnostics() {
+        eprint!("{}", renderer.render(&Diagnostic::from(err), &map));
+    }
+
+    Ok(tokens.iter().any(|tok| tok.kind == TokenKind::Invalid))
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("mlexer: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if options.help {
+        let _ = writeln!(io::stdout(), "{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let stdout = io::stdout();
+    let mut printer = Printer::new(&options.format, BufWriter::new(stdout.lock()));
+    let mut invalid = false;
+
+    for file in &options.files {
+        match dump(file, &options, &mut printer) {
+            Ok(found) => invalid |= found,
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return ExitCode::SUCCESS,
+            Err(err) => {
+                let _ = printer.finish();
+                eprintln!("mlexer: {}", err);
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    if printer.finish().is_err() {
+        return ExitCode::from(2);
+    }
+    if invalid && options.deny_invalid {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_options() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+
+        let options = parse(&[
+            "-w",
+            "-k",
+            "identifier",
+            "--kind",
+            "Keyword",
+            "-f",
+            "jsonl",
+            "a.src",
+        ])
+        .unwrap();
+        assert_eq!(options.format, Format::JsonLines);
+        assert_eq!(options.files, ["a.src"]);
+        assert!(options.shows(TokenKind::Identifier));
+        assert!(options.shows(TokenKind::Keyword(3)));
+        assert!(!options.shows(TokenKind::Whitespace));
+        assert!(!options.shows(TokenKind::Int));
+
+        let options = parse(&["-w"]).unwrap();
+        assert_eq!(options.files, ["-"]);
+        assert!(options.shows(TokenKind::Int) && !options.shows(TokenKind::Whitespace));
+
+        assert_eq!(
+            parse(&["-k", "bogus"]).err().unwrap(),
+            "unknown token kind `bogus`"
+        );
+        assert!(parse(&["-k"]).is_err());
+        assert!(parse(&["-f", "xml"]).is_err());
+        assert!(parse(&["--bad"]).is_err());
+    }
+}
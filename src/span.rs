@@ -0,0 +1,166 @@
+use core::fmt;
+
+use crate::Token;
+
+/// Byte range `start..end` into the lexer input.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Moves the span `offset` bytes further into the input.
+    pub fn shift(&self, offset: usize) -> Span {
+        Span::new(self.start + offset, self.end + offset)
+    }
+
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    pub fn slice<'s>(&self, source: &'s [u8]) -> &'s [u8] {
+        &source[self.start..self.end]
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// One-based line and column.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for LineCol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
+}
+
+/// Resolves byte offsets into `source` to lines and columns.
+#[derive(Debug, Clone)]
+pub struct SourceMap<'s> {
+    source: &'s [u8],
+    line_starts: Vec<usize>,
+}
+
+impl<'s> SourceMap<'s> {
+    pub fn new(source: &'s [u8]) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(
+                source
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &b)| b == b'\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub fn source(&self) -> &'s [u8] {
+        self.source
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Span of the one-based `line`, without its line terminator.
+    ///
+    /// Panics if `line` is 0 or greater than [`SourceMap::line_count`].
+    pub fn line_span(&self, line: usize) -> Span {
+        let start = self.line_starts[line - 1];
+        let mut end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |&next| next - 1);
+        if end > start && self.source[end - 1] == b'\r' {
+            end -= 1;
+        }
+        Span::new(start, end)
+    }
+
+    pub fn line(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+
+    /// Line and column of `offset`, counting columns in characters.
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let (line, prefix) = self.line_prefix(offset);
+        let col = prefix.iter().filter(|&&b| b & 0xc0 != 0x80).count() + 1;
+        LineCol { line, col }
+    }
+
+    /// Line and column of `offset`, counting columns in bytes.
+    pub fn line_col_utf8(&self, offset: usize) -> LineCol {
+        let (line, prefix) = self.line_prefix(offset);
+        LineCol {
+            line,
+            col: prefix.len() + 1,
+        }
+    }
+
+    /// Line and column of `offset`, counting columns in UTF-16 code units.
+    pub fn line_col_utf16(&self, offset: usize) -> LineCol {
+        let (line, prefix) = self.line_prefix(offset);
+        let col = String::from_utf8_lossy(prefix)
+            .chars()
+            .map(char::len_utf16)
+            .sum::<usize>()
+            + 1;
+        LineCol { line, col }
+    }
+
+    /// Formats `tok` as `row:col Kind -> value`.
+    pub fn display<'t>(&'t self, tok: &'t Token<'_>) -> impl fmt::Display + 't {
+        TokenDisplay { map: self, tok }
+    }
+
+    fn line_prefix(&self, offset: usize) -> (usize, &'s [u8]) {
+        let offset = offset.min(self.source.len());
+        let line = self.line(offset);
+        (line, &self.source[self.line_starts[line - 1]..offset])
+    }
+}
+
+struct TokenDisplay<'t, 'a> {
+    map: &'t SourceMap<'t>,
+    tok: &'t Token<'a>,
+}
+
+impl fmt::Display for TokenDisplay<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:?} -> {}",
+            self.map.line_col(self.tok.span.start),
+            self.tok.kind,
+            self.tok.value
+        )
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::{self, Read},
+    iter::FusedIterator,
+};
+
+use crate::{LexError, Lexer, LexerConfig, Newlines, Span, Token, TokenKind};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Bytes past the end of a token the lexer may have looked at to end it,
+/// on top of the longest registered delimiter. Merged line breaks look
+/// further and are held back until followed by a non-blank character.
+const LOOKAHEAD: usize = 32;
+
+/// Lexes input pulled from an `io::Read` in chunks, keeping in memory only
+/// the chunk being lexed and the token straddling its end.
+///
+/// Tokens are owned and their spans are offsets from the start of the stream.
+pub struct StreamLexer<R> {
+    reader: R,
+    config: LexerConfig,
+    chunk_size: usize,
+    margin: usize,
+
+    buffer: Vec<u8>,
+    offset: usize,
+    cursor: usize,
+    eof: bool,
+    finished: bool,
+
+    pending: VecDeque<Token<'static>>,
+    diagnostics: Vec<LexError>,
+}
+
+impl<R: Read> StreamLexer<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_config(reader, LexerConfig::default())
+    }
+
+    pub fn with_config(reader: R, config: LexerConfig) -> Self {
+        Self {
+            reader,
+            margin: LOOKAHEAD + config.longest_delimiter(),
+            config,
+            chunk_size: CHUNK_SIZE,
+            buffer: vec![],
+            offset: 0,
+            cursor: 0,
+            eof: false,
+            finished: false,
+            pending: VecDeque::new(),
+            diagnostics: vec![],
+        }
+    }
+
+    /// Number of bytes read from the underlying reader at a time.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    /// Errors found so far, in input order.
+    pub fn diagnostics(&self) -> &[LexError] {
+        &self.diagnostics
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<LexError> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let target = self.buffer.len() + self.chunk_size;
+
+        while !self.eof && self.buffer.len() < target {
+            let len = self.buffer.len();
+            self.buffer.resize(target, 0);
+            match self.reader.read(&mut self.buffer[len..]) {
+                Ok(0) => {
+                    self.buffer.truncate(len);
+                    self.eof = true;
+                }
+                Ok(read) => self.buffer.truncate(len + read),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => self.buffer.truncate(len),
+                Err(err) => {
+                    self.buffer.truncate(len);
+                    return Err(err);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lexes the buffered input, queueing the tokens that can no longer be
+    /// extended by input not read yet.
+    fn lex_buffer(&mut self) -> io::Result<()> {
+        self.fill()?;
+
+        let mut config = std::mem::take(&mut self.config);
+        let emit_eof = std::mem::replace(&mut config.emit_eof, false);
+        let recover = config.recover;
+        let merge_newlines = config.newlines == Newlines::Merge;
+
+        let limit = if self.eof {
+            self.buffer.len()
+        } else {
+            self.buffer.len().saturating_sub(self.margin)
+        };
+
+        let mut lexer = Lexer::with_config(&self.buffer[..], config);
+        lexer.seek(self.cursor);
+
+        let mut committed = self.cursor;
+        let mut aborted = false;
+        while let Some(mut tok) = lexer.next() {
+            if tok.span.end > limit {
+                break;
+            }
+            // A merged line break absorbs any blank lines read later.
+            if merge_newlines
+                && tok.kind == TokenKind::Newline
+                && !self.eof
+                && lexer.blank_after(tok.span.end)
+            {
+                break;
+            }
+            committed = tok.span.end;
+            aborted = tok.kind == TokenKind::Invalid && !recover;
+            tok.span = tok.span.shift(self.offset);
+            self.pending.push_back(tok.into_owned());
+            if aborted {
+                break;
+            }
+        }
+
+        let (mut config, diagnostics) = lexer.into_parts();
+        config.emit_eof = emit_eof;
+        self.config = config;
+
+        let eof = self.eof;
+        self.diagnostics.extend(
+            diagnostics
+                .into_iter()
+                .filter(|err| eof || err.span().start < committed)
+                .map(|err| err.shift(self.offset)),
+        );
+
+        if aborted || self.eof {
+            self.finished = true;
+            if emit_eof {
+                // Like `Lexer`, end with `Null` at the very end of the input,
+                // even past an `Invalid` token that stopped lexing.
+                let unread = if self.eof {
+                    0
+                } else {
+                    io::copy(&mut self.reader, &mut io::sink())? as usize
+                };
+                let end = self.offset + self.buffer.len() + unread;
+                self.pending.push_back(Token::new(
+                    TokenKind::Null,
+                    Cow::Borrowed(""),
+                    Span::new(end, end),
+                ));
+            }
+        }
+
+        // Keep the byte before the next token, which some rules look back at.
+        let drained = committed.saturating_sub(1);
+        self.buffer.drain(..drained);
+        self.offset += drained;
+        self.cursor = committed - drained;
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for StreamLexer<R> {
+    type Item = io::Result<Token<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(tok) = self.pending.pop_front() {
+                return Some(Ok(tok));
+            }
+            if self.finished {
+                return None;
+            }
+            if let Err(err) = self.lex_buffer() {
+                self.finished = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+impl<R: Read> FusedIterator for StreamLexer<R> {}
@@ -1,16 +1,983 @@
 use super::*;
-
+use std::io;
 
 #[test]
 fn test() {
-    let mut lex = Lexer::new("1 + 2 * 3 asdsda\n ds".to_string().into_bytes());
-    loop {
-        let tok = lex.next();
+    let lex = Lexer::new("1 + 2 * 3 asdsda\n ds".to_string().into_bytes());
+    for tok in lex {
         println!("{:?}", tok);
         println!("{}", tok);
+    }
+}
+
+fn kinds(lex: Lexer<'_>) -> Vec<(TokenKind, String)> {
+    lex.take_while(|tok| tok.kind != TokenKind::Invalid)
+        .filter(|tok| tok.kind != TokenKind::Whitespace)
+        .map(|tok| (tok.kind, tok.value.into_owned()))
+        .collect()
+}
+
+#[test]
+fn config_char_classes() {
+    let config = LexerConfig::new()
+        .ident_start(|ch| ch.is_ascii_alphabetic() || ch == '$')
+        .ident_continue(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+        .whitespace(|ch| ch.is_ascii_whitespace() || ch == ',')
+        .operator(|ch| "+*".contains(ch));
+    let lex = Lexer::with_config(b"$a-1, b + c;".to_vec(), config);
+
+    assert_eq!(
+        kinds(lex),
+        vec![
+            (TokenKind::Identifier, "$a-1".to_string()),
+            (TokenKind::Identifier, "b".to_string()),
+            (TokenKind::Op, "+".to_string()),
+            (TokenKind::Identifier, "c".to_string()),
+            (TokenKind::Ponct, ";".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn operators_longest_match() {
+    let config = LexerConfig::new().operators(["=", "==", "<<", "<<=", "->", "..."]);
+    let lex = Lexer::with_config(b"a <<= b == c->d .. e".to_vec(), config);
+
+    assert_eq!(
+        kinds(lex),
+        vec![
+            (TokenKind::Identifier, "a".to_string()),
+            (TokenKind::Op, "<<=".to_string()),
+            (TokenKind::Identifier, "b".to_string()),
+            (TokenKind::Op, "==".to_string()),
+            (TokenKind::Identifier, "c".to_string()),
+            (TokenKind::Op, "->".to_string()),
+            (TokenKind::Identifier, "d".to_string()),
+            (TokenKind::Ponct, ".".to_string()),
+            (TokenKind::Ponct, ".".to_string()),
+            (TokenKind::Identifier, "e".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn string_literals() {
+    let config = LexerConfig::new().raw_string('`');
+    let mut lex = Lexer::with_config(br#""a\tb\"\\ \x41\u{1F600}" `c:\n`"#.to_vec(), config);
+
+    let tok = lex.next().unwrap();
+    assert_eq!(tok.kind, TokenKind::String);
+    assert_eq!(tok.value, r#""a\tb\"\\ \x41\u{1F600}""#);
+    assert_eq!(
+        tok.literal,
+        Some(Literal::Str("a\tb\"\\ A\u{1F600}".into()))
+    );
+
+    lex.next().unwrap();
+    let tok = lex.next().unwrap();
+    assert_eq!(tok.kind, TokenKind::String);
+    assert_eq!(tok.literal, Some(Literal::Str(r"c:\n".into())));
+}
+
+#[test]
+fn string_unterminated_or_bad_escape() {
+    let tok = Lexer::new(b"\"abc".to_vec()).next().unwrap();
+    assert_eq!(
+        (tok.kind, tok.value.as_ref()),
+        (TokenKind::Invalid, "\"abc")
+    );
+
+    let tok = Lexer::new(b"\"a\\qb\"".to_vec()).next().unwrap();
+    assert_eq!(
+        (tok.kind, tok.value.as_ref()),
+        (TokenKind::Invalid, "\"a\\q")
+    );
+
+    for input in [&b"\"\\x+1\""[..], b"\"\\u{+41}\""] {
+        let mut lex = Lexer::with_config(input, LexerConfig::new().recover(true));
+        assert_eq!(lex.next().unwrap().kind, TokenKind::Invalid);
+        assert!(matches!(lex.diagnostics(), [LexError::InvalidEscape(_)]));
+    }
+}
+
+#[test]
+fn char_literals() {
+    let mut lex = Lexer::new("'a' '\\n' '\\u{263A}' 'é' 'a".as_bytes().to_vec());
+    let mut chars = vec![];
+    loop {
+        let tok = lex.next().unwrap();
+        match tok.kind {
+            TokenKind::Char => chars.push(tok.literal),
+            TokenKind::Whitespace => {}
+            _ => {
+                assert_eq!((tok.kind, tok.value.as_ref()), (TokenKind::Ponct, "'"));
+                break;
+            }
+        }
+    }
+
+    assert_eq!(
+        chars,
+        ['a', '\n', '\u{263A}', 'é'].map(|ch| Some(Literal::Char(ch)))
+    );
+    assert_eq!(lex.next().unwrap().value, "a");
+
+    let tok = Lexer::new(b"''".to_vec()).next().unwrap();
+    assert_eq!(tok.kind, TokenKind::Invalid);
+
+    for (input, escape) in [("'\\q' x", Span::new(1, 3)), ("'\\é' x", Span::new(1, 4))] {
+        let mut lex = Lexer::with_config(input.as_bytes(), LexerConfig::new().recover(true));
+        let tok = lex.next().unwrap();
+        assert_eq!(
+            (tok.kind, tok.span.end),
+            (TokenKind::Invalid, escape.end + 1)
+        );
+        assert_eq!(lex.diagnostics(), [LexError::InvalidEscape(escape)]);
+    }
+
+    let mut lex = Lexer::with_config(&b"'\\x+7'"[..], LexerConfig::new().recover(true));
+    assert_eq!(lex.next().unwrap().kind, TokenKind::Invalid);
+    assert_eq!(
+        lex.diagnostics(),
+        [LexError::InvalidEscape(Span::new(1, 3))]
+    );
+}
+
+#[test]
+fn comments() {
+    let config = || {
+        LexerConfig::new()
+            .line_comment("//")
+            .line_comment("#")
+            .doc_comment("///")
+            .nested_block_comment("/*", "*/")
+    };
+    let input = b"a // one\n/// doc\n# hash\nb /* x /* y */ z */ c";
+
+    assert_eq!(
+        kinds(Lexer::with_config(input.to_vec(), config())),
+        vec![
+            (TokenKind::Identifier, "a".to_string()),
+            (TokenKind::Identifier, "b".to_string()),
+            (TokenKind::Identifier, "c".to_string()),
+        ]
+    );
+
+    let tokens = kinds(Lexer::with_config(
+        input.to_vec(),
+        config().emit_comments(true),
+    ));
+    assert_eq!(
+        tokens[1..5],
+        [
+            (TokenKind::Comment, "// one".to_string()),
+            (TokenKind::DocComment, "/// doc".to_string()),
+            (TokenKind::Comment, "# hash".to_string()),
+            (TokenKind::Identifier, "b".to_string()),
+        ]
+    );
+    assert_eq!(
+        tokens[5],
+        (TokenKind::Comment, "/* x /* y */ z */".to_string())
+    );
+
+    let tok = Lexer::with_config(b"/* open".to_vec(), config())
+        .next()
+        .unwrap();
+    assert_eq!(tok.kind, TokenKind::Invalid);
+}
+
+#[derive(PartialEq, Debug)]
+enum Kw {
+    If,
+    While,
+    Fn,
+}
+
+impl TryFrom<usize> for Kw {
+    type Error = ();
+
+    fn try_from(id: usize) -> Result<Self, ()> {
+        match id {
+            0 => Ok(Kw::If),
+            1 => Ok(Kw::While),
+            2 => Ok(Kw::Fn),
+            _ => Err(()),
+        }
+    }
+}
+
+#[test]
+fn keywords() {
+    let config = LexerConfig::new()
+        .keywords(["if", "while"])
+        .keywords(["fn", "if"]);
+    let out: Vec<_> = Lexer::with_config(b"fn iff while if".to_vec(), config)
+        .filter(|tok| tok.kind != TokenKind::Whitespace)
+        .map(|tok| (tok.kind, tok.keyword::<Kw>()))
+        .collect();
+
+    assert_eq!(
+        out,
+        vec![
+            (TokenKind::Keyword(2), Some(Kw::Fn)),
+            (TokenKind::Identifier, None),
+            (TokenKind::Keyword(1), Some(Kw::While)),
+            (TokenKind::Keyword(0), Some(Kw::If)),
+        ]
+    );
+}
+
+#[test]
+fn unicode_identifiers() {
+    let input = "x1 café _ñ2 αβγ".as_bytes();
+    let idents = |config| {
+        kinds(Lexer::with_config(input.to_vec(), config))
+            .into_iter()
+            .map(|(kind, value)| format!("{:?} {}", kind, value))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        idents(LexerConfig::new()),
+        [
+            "Identifier x1",
+            "Identifier café",
+            "Identifier _ñ2",
+            "Identifier αβγ"
+        ]
+    );
+    assert_eq!(
+        idents(LexerConfig::new().ascii_identifiers()),
+        ["Identifier x1", "Identifier caf"]
+    );
+}
+
+#[test]
+fn utf8_decoding() {
+    let mut lex = Lexer::new("éé «".as_bytes().to_vec());
+    let tok = lex.next().unwrap();
+    assert_eq!((tok.value.as_ref(), tok.span), ("éé", Span::new(0, 4)));
+    lex.next().unwrap();
+    let tok = lex.next().unwrap();
+    assert_eq!((tok.kind, tok.value.as_ref()), (TokenKind::Invalid, "«"));
+
+    let mut lex = Lexer::new(b"ab \xe2\x82 c".to_vec());
+    lex.next().unwrap();
+    lex.next().unwrap();
+    let tok = lex.next().unwrap();
+    assert_eq!(
+        (tok.kind, tok.value.as_ref()),
+        (TokenKind::Invalid, "\u{FFFD}")
+    );
+
+    let tok = Lexer::new(b"\"a\xffb\"".to_vec()).next().unwrap();
+    assert_eq!(tok.kind, TokenKind::Invalid);
+}
+
+#[test]
+fn token_positions() {
+    let source = b"ab +\n  \"x\ny\" 12";
+    let out: Vec<_> = Lexer::new(&source[..]).collect();
+
+    let map = SourceMap::new(source);
+    let expected = [
+        ("ab", (1, 1), (1, 3), (0, 2)),
+        (" ", (1, 3), (1, 4), (2, 3)),
+        ("+", (1, 4), (1, 5), (3, 4)),
+        ("\n  ", (1, 5), (2, 3), (4, 7)),
+        ("\"x\ny\"", (2, 3), (3, 3), (7, 12)),
+        (" ", (3, 3), (3, 4), (12, 13)),
+        ("12", (3, 4), (3, 6), (13, 15)),
+    ];
+    assert_eq!(out.len(), expected.len());
+    for (tok, (value, start, end, span)) in out.iter().zip(expected) {
+        let start_lc = map.line_col(tok.span.start);
+        let end_lc = map.line_col(tok.span.end);
+        assert_eq!(
+            (
+                tok.value.as_ref(),
+                (start_lc.line, start_lc.col),
+                (end_lc.line, end_lc.col)
+            ),
+            (value, start, end)
+        );
+        assert_eq!(tok.span, Span::new(span.0, span.1));
+        assert_eq!(tok.span.slice(map.source()), value.as_bytes());
+    }
+    assert_eq!(map.display(&out[6]).to_string(), "3:4 Int -> 12");
+    assert_eq!(out[6].to_string(), "13..15 Int -> 12");
+}
+
+#[test]
+fn source_map_columns() {
+    let source = "a\r\né😀x\n".as_bytes();
+    let map = SourceMap::new(source);
+
+    assert_eq!(map.line_count(), 3);
+    assert_eq!(map.line_span(1), Span::new(0, 1));
+    assert_eq!(map.line_col(9), LineCol { line: 2, col: 3 });
+    assert_eq!(map.line_col_utf16(9), LineCol { line: 2, col: 4 });
+    assert_eq!(map.line_col_utf8(9), LineCol { line: 2, col: 7 });
+    assert_eq!(map.line_col(source.len()), LineCol { line: 3, col: 1 });
+}
+
+#[test]
+fn borrowed_tokens() {
+    let source = String::from(r#"name "plain" "esc\n""#);
+    let mut lex = Lexer::new(source.as_bytes());
+
+    let tok = lex.next().unwrap();
+    assert!(matches!(tok.value, Cow::Borrowed("name")));
+    lex.next().unwrap();
+    let tok = lex.next().unwrap();
+    assert!(matches!(
+        tok.literal,
+        Some(Literal::Str(Cow::Borrowed("plain")))
+    ));
+    lex.next().unwrap();
+    let tok = lex.next().unwrap();
+    assert!(matches!(tok.literal, Some(Literal::Str(Cow::Owned(ref s))) if s == "esc\n"));
+
+    let owned = Lexer::new(source.clone().into_bytes()).next().unwrap();
+    assert!(matches!(owned.value, Cow::Owned(_)));
+    let tok: Token<'static> = Lexer::new(source.as_bytes()).next().unwrap().into_owned();
+    assert_eq!(tok, owned);
+}
+
+#[test]
+fn iterator_eof() {
+    let mut lex = Lexer::new(&b"a"[..]);
+    assert_eq!(lex.next().map(|tok| tok.kind), Some(TokenKind::Identifier));
+    assert_eq!(lex.next(), None);
+    assert_eq!(lex.next(), None);
+
+    let config = LexerConfig::new().emit_eof(true);
+    let kinds: Vec<_> = Lexer::with_config(&b"a"[..], config)
+        .map(|tok| (tok.kind, tok.span))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            (TokenKind::Identifier, Span::new(0, 1)),
+            (TokenKind::Null, Span::new(1, 1)),
+        ]
+    );
+}
+
+#[test]
+fn error_recovery() {
+    let input = "a §¤ b \"x\\qy\" '\\q' c \u{7f}\u{7f}d".as_bytes();
+    let tokens = |config| -> Vec<_> {
+        Lexer::with_config(input, config)
+            .filter(|tok| tok.kind != TokenKind::Whitespace)
+            .map(|tok| (tok.kind, tok.value.into_owned()))
+            .collect()
+    };
+
+    assert_eq!(
+        tokens(LexerConfig::new()),
+        [
+            (TokenKind::Identifier, "a".to_string()),
+            (TokenKind::Invalid, "§".to_string()),
+        ]
+    );
+    assert_eq!(
+        tokens(LexerConfig::new().recover(true)),
+        [
+            (TokenKind::Identifier, "a".to_string()),
+            (TokenKind::Invalid, "§¤".to_string()),
+            (TokenKind::Identifier, "b".to_string()),
+            (TokenKind::Invalid, "\"x\\qy\"".to_string()),
+            (TokenKind::Invalid, "'\\q'".to_string()),
+            (TokenKind::Identifier, "c".to_string()),
+            (TokenKind::Invalid, "\u{7f}\u{7f}".to_string()),
+            (TokenKind::Identifier, "d".to_string()),
+        ]
+    );
+
+    let tokens: Vec<_> = Lexer::with_config(&b"x\xff\xfey"[..], LexerConfig::new().recover(true))
+        .map(|tok| (tok.kind, tok.span))
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            (TokenKind::Identifier, Span::new(0, 1)),
+            (TokenKind::Invalid, Span::new(1, 3)),
+            (TokenKind::Identifier, Span::new(3, 4)),
+        ]
+    );
+}
+
+#[test]
+fn diagnostics() {
+    let config = LexerConfig::new().recover(true).block_comment("/*", "*/");
+    let mut lex = Lexer::with_config(&b"a \xff\xfe\x7f\x7f \"\\q\" '' /* x"[..], config);
+    let kinds: Vec<_> = lex.by_ref().map(|tok| tok.kind).collect();
+
+    assert_eq!(
+        kinds.iter().filter(|&&k| k == TokenKind::Invalid).count(),
+        4
+    );
+    assert_eq!(
+        lex.diagnostics(),
+        [
+            LexError::InvalidUtf8(Span::new(2, 3)),
+            LexError::InvalidUtf8(Span::new(3, 4)),
+            LexError::UnexpectedChar(Span::new(4, 6)),
+            LexError::InvalidEscape(Span::new(8, 10)),
+            LexError::InvalidChar(Span::new(12, 14)),
+            LexError::UnterminatedComment(Span::new(15, 19)),
+        ]
+    );
 
-        if matches!(tok.kind, TokenKind::Null | TokenKind::Invalid)  {
-            break;
+    let err = &lex.diagnostics()[3];
+    assert_eq!(err.code(), "E0004");
+    assert_eq!(err.to_string(), "invalid escape sequence at 8..10");
+    let _: &dyn std::error::Error = err;
+
+    assert_eq!(lex.take_diagnostics().len(), 6);
+    assert!(lex.diagnostics().is_empty());
+}
+
+#[test]
+fn render_diagnostics() {
+    let source = b"let s = \"a\\qb\";\nlet t\t= 1;\n";
+    let map = SourceMap::new(source);
+    let err = LexError::InvalidEscape(Span::new(10, 12));
+
+    let rendered = Renderer::new().origin("main.src").render(
+        &Diagnostic::from(&err).with_note("valid escapes are \\n, \\t, \\\\"),
+        &map,
+    );
+    assert_eq!(
+        rendered,
+        "error[E0004]: invalid escape sequence\n \
+         --> main.src:1:11\n  \
+         |\n\
+         1 | let s = \"a\\qb\";\n  \
+         |           ^^ invalid escape sequence\n  \
+         |\n  \
+         = note: valid escapes are \\n, \\t, \\\\\n"
+    );
+
+    let diagnostic = Diagnostic::warning("unused binding")
+        .with_label(Span::new(20, 21), "never read")
+        .with_secondary_label(Span::new(4, 5), "");
+    let rendered = Renderer::new().render(&diagnostic, &map);
+    assert_eq!(
+        rendered,
+        "warning: unused binding\n \
+         --> 2:5\n  \
+         |\n\
+         1 | let s = \"a\\qb\";\n  \
+         |     -\n\
+         2 | let t    = 1;\n  \
+         |     ^ never read\n"
+    );
+
+    let colored = Renderer::new().colors(true).render(&diagnostic, &map);
+    assert!(colored.starts_with("\x1b[1;33mwarning\x1b[0m"));
+
+    let config = LexerConfig::new()
+        .whitespace(|ch| ch == ' ' || ch == '\r')
+        .recover(true);
+    let mut lex = Lexer::with_config(&b"a \r\nb"[..], config);
+    lex.by_ref().for_each(drop);
+    let map = lex.source_map();
+    let rendered = Renderer::new().render(&Diagnostic::from(&lex.diagnostics()[0]), &map);
+    assert!(rendered.ends_with("1 | a \n  |   ^ unexpected character\n"));
+
+    let map = SourceMap::new(b"ab");
+    let diagnostic = Diagnostic::error("expected `;`").with_label(Span::new(5, 6), "here");
+    let rendered = Renderer::new().render(&diagnostic, &map);
+    assert!(rendered.ends_with("1 | ab\n  |   ^ here\n"));
+}
+
+#[test]
+fn number_literals() {
+    let input = "0x1F 0o755 0b1010 1_000_000 6.02e23 1E-9 10u32 1.5f64 0xffu8 1..2 .5";
+    let tokens: Vec<_> = Lexer::with_config(
+        input.as_bytes(),
+        LexerConfig::new().leading_dot_floats(true),
+    )
+    .filter(|tok| tok.kind != TokenKind::Whitespace)
+    .map(|tok| {
+        (
+            tok.kind,
+            tok.value.into_owned(),
+            tok.suffix.map(Cow::into_owned),
+        )
+    })
+    .collect();
+
+    let int = |value: &str, suffix: Option<&str>| {
+        (
+            TokenKind::Int,
+            value.to_string(),
+            suffix.map(str::to_string),
+        )
+    };
+    let float = |value: &str, suffix: Option<&str>| {
+        (
+            TokenKind::Float,
+            value.to_string(),
+            suffix.map(str::to_string),
+        )
+    };
+    assert_eq!(
+        tokens,
+        [
+            int("0x1F", None),
+            int("0o755", None),
+            int("0b1010", None),
+            int("1_000_000", None),
+            float("6.02e23", None),
+            float("1E-9", None),
+            int("10u32", Some("u32")),
+            float("1.5f64", Some("f64")),
+            int("0xffu8", Some("u8")),
+            int("1", None),
+            (TokenKind::Ponct, ".".to_string(), None),
+            (TokenKind::Ponct, ".".to_string(), None),
+            int("2", None),
+            float(".5", None),
+        ]
+    );
+}
+
+#[test]
+fn malformed_numbers() {
+    let mut lex = Lexer::with_config(
+        &b"0x 0b102 1e+ 7 1e 1.5E 0b2"[..],
+        LexerConfig::new().recover(true),
+    );
+    let kinds: Vec<_> = lex
+        .by_ref()
+        .filter(|tok| tok.kind != TokenKind::Whitespace)
+        .map(|tok| (tok.kind, tok.value.into_owned()))
+        .collect();
+
+    assert_eq!(
+        kinds,
+        [
+            (TokenKind::Invalid, "0x".to_string()),
+            (TokenKind::Invalid, "0b102".to_string()),
+            (TokenKind::Invalid, "1e+".to_string()),
+            (TokenKind::Int, "7".to_string()),
+            (TokenKind::Invalid, "1e".to_string()),
+            (TokenKind::Invalid, "1.5E".to_string()),
+            (TokenKind::Invalid, "0b2".to_string()),
+        ]
+    );
+    assert_eq!(
+        lex.diagnostics(),
+        [
+            LexError::MissingDigits(Span::new(0, 2)),
+            LexError::InvalidDigit(Span::new(7, 8)),
+            LexError::MalformedExponent(Span::new(10, 12)),
+            LexError::MalformedExponent(Span::new(16, 17)),
+            LexError::MalformedExponent(Span::new(21, 22)),
+            LexError::InvalidDigit(Span::new(25, 26)),
+        ]
+    );
+}
+
+#[test]
+fn number_values() {
+    let input = "0x1F 0b1010_1010 1_000_000u32 18446744073709551615 0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff_f 6.02e23 .5 1e999";
+    let tokens: Vec<_> = Lexer::with_config(
+        input.as_bytes(),
+        LexerConfig::new().leading_dot_floats(true),
+    )
+    .filter(|tok| tok.kind != TokenKind::Whitespace)
+    .collect();
+
+    assert_eq!(tokens[0].as_i64(), Some(Ok(31)));
+    assert_eq!(tokens[1].as_u64(), Some(Ok(0b1010_1010)));
+    assert_eq!(tokens[2].as_u64(), Some(Ok(1_000_000)));
+    assert_eq!(tokens[3].as_u64(), Some(Ok(u64::MAX)));
+    assert_eq!(
+        tokens[3].as_i64(),
+        Some(Err(LexError::NumberOverflow(tokens[3].span)))
+    );
+    assert_eq!(tokens[3].as_i128(), Some(Ok(u64::MAX as i128)));
+
+    let big = tokens[4].int_value().unwrap();
+    assert_eq!((big.radix, big.digits.len()), (16, 33));
+    assert!(big.to_u128().is_err());
+    assert_eq!(big.to_f64(), Ok(16f64.powi(33) - 1.0));
+
+    for digits in ["99999999999999999999999", "123456789012345678901234567890"] {
+        let tok = Lexer::new(digits.as_bytes()).next().unwrap();
+        assert_eq!(tok.as_f64(), Some(Ok(digits.parse().unwrap())));
+    }
+    let tok = Lexer::new(&b"0x1fffffffffffff1"[..]).next().unwrap();
+    assert_eq!(tok.as_f64(), Some(Ok(0x1fffffffffffff1u64 as f64)));
+
+    assert_eq!(tokens[5].as_f64(), Some(Ok(6.02e23)));
+    assert_eq!(tokens[5].as_i64(), None);
+    assert_eq!(tokens[6].as_f64(), Some(Ok(0.5)));
+    assert_eq!(
+        tokens[7].as_f64(),
+        Some(Err(LexError::NumberOverflow(tokens[7].span)))
+    );
+}
+
+/// Reader handing out its input a few bytes at a time.
+struct Trickle<'a>(&'a [u8]);
+
+impl io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.0.len()).min(3);
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn stream_lexer_matches_lexer() {
+    let config = |newlines, recover| {
+        LexerConfig::new()
+            .recover(recover)
+            .emit_eof(true)
+            .operators(["<<=", "=="])
+            .block_comment("/*", "*/")
+            .line_comment("//")
+            .keywords(["let"])
+            .newlines(newlines)
+    };
+    let mut input = "let café = \"a\\u{1F600}b\" <<= 0x1F_u8 // trailing\n/* long block */ 1e+ '\\u{263A}' § 6.02e23 x == y\n"
+        .repeat(40);
+    input.push_str(&format!("a\n{}\n\r\n  b\n\n", " ".repeat(100)));
+    let input = input.into_bytes();
+
+    let modes = [
+        (Newlines::Whitespace, true),
+        (Newlines::Merge, true),
+        (Newlines::Whitespace, false),
+    ];
+    for (newlines, recover) in modes {
+        let mut lexer = Lexer::with_config(&input[..], config(newlines, recover));
+        let expected: Vec<_> = lexer.by_ref().map(Token::into_owned).collect();
+
+        for chunk_size in [1, 7, 8, 64, 4096] {
+            let mut stream = StreamLexer::with_config(Trickle(&input), config(newlines, recover))
+                .chunk_size(chunk_size);
+            let tokens: Vec<_> = stream.by_ref().collect::<io::Result<_>>().unwrap();
+
+            assert_eq!(
+                tokens, expected,
+                "{:?} recover {} chunk size {}",
+                newlines, recover, chunk_size
+            );
+            assert_eq!(stream.diagnostics(), lexer.diagnostics());
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn stream_lexer_stops_at_invalid() {
+    let mut stream = StreamLexer::new(Trickle(b"a \x7f b")).chunk_size(1);
+    let kinds: Vec<_> = stream.by_ref().map(|tok| tok.unwrap().kind).collect();
+
+    assert_eq!(
+        kinds,
+        [
+            TokenKind::Identifier,
+            TokenKind::Whitespace,
+            TokenKind::Invalid
+        ]
+    );
+    assert_eq!(
+        stream.diagnostics(),
+        [LexError::UnexpectedChar(Span::new(2, 3))]
+    );
+}
+
+#[test]
+fn stream_lexer_trailing_comment() {
+    let config = LexerConfig::new().line_comment("#");
+    let stream = StreamLexer::with_config(Trickle(b"a # c"), config);
+
+    assert_eq!(stream.count(), 2);
+}
+
+#[test]
+fn lexer_from_path() {
+    let path = std::env::temp_dir().join(format!("mlexer-{}.src", std::process::id()));
+    std::fs::write(&path, "let x = 1").unwrap();
+    let lex = Lexer::from_path(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(kinds(lex).len(), 4);
+    assert_eq!(Lexer::from_path("/dev/null").unwrap().count(), 0);
+
+    let err = Lexer::from_path(&path).err().unwrap();
+    assert_eq!(err.path(), path);
+    assert_eq!(err.io_error().kind(), io::ErrorKind::NotFound);
+    assert!(err.to_string().starts_with(&path.display().to_string()));
+}
+
+#[test]
+fn json_output() {
+    let source = b"k 'x'\n\"a\\\"\" 1.5";
+    let config = LexerConfig::new().keywords(["k"]);
+    let tokens: Vec<_> = Lexer::with_config(&source[..], config)
+        .filter(|tok| tok.kind != TokenKind::Whitespace)
+        .collect();
+    let map = SourceMap::new(source);
+
+    let mut json = JsonWriter::lines(vec![]);
+    for tok in &tokens {
+        json.write(tok, &map).unwrap();
+    }
+    assert_eq!(
+        String::from_utf8(json.finish().unwrap()).unwrap(),
+        [
+            r#"{"kind":"Keyword","keyword":0,"text":"k","span":[0,1],"line":1,"col":1,"value":null}"#,
+            r#"{"kind":"Char","text":"'x'","span":[2,5],"line":1,"col":3,"value":"x"}"#,
+            r#"{"kind":"String","text":"\"a\\\"\"","span":[6,11],"line":2,"col":1,"value":"a\""}"#,
+            r#"{"kind":"Float","text":"1.5","span":[12,15],"line":2,"col":7,"value":1.5}"#,
+            "",
+        ]
+        .join("\n")
+    );
+
+    let mut json = JsonWriter::new(vec![]);
+    json.write(&tokens[3], &map).unwrap();
+    let out = String::from_utf8(json.finish().unwrap()).unwrap();
+    assert!(out.starts_with("[\n{") && out.ends_with("}\n]\n"));
+    assert_eq!(JsonWriter::new(vec![]).finish().unwrap(), b"[]\n");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    let config = LexerConfig::new().keywords(["fn"]);
+    let tokens: Vec<_> = Lexer::with_config(&b"fn f(\"a\\n\", 10u8)"[..], config).collect();
+
+    let json = serde_json::to_string(&tokens).unwrap();
+    let back: Vec<Token<'static>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, tokens);
+
+    let loc = SourceMap::new(b"a\nb").line_col(2);
+    assert_eq!(
+        serde_json::to_string(&loc).unwrap(),
+        r#"{"line":2,"col":1}"#
+    );
+}
+
+#[test]
+fn binary_round_trip() {
+    let config = LexerConfig::new()
+        .keywords(["fn"])
+        .line_comment("//")
+        .emit_comments(true);
+    let source = "fn f(x) { 'é' + \"a\\n\" + 10u8 + x + x } // ok\n@";
+    let tokens: Vec<_> = Lexer::with_config(source.as_bytes(), config).collect();
+
+    let mut writer = TokenWriter::new(vec![]).unwrap();
+    for tok in &tokens {
+        writer.write(tok).unwrap();
+    }
+    let bytes = writer.finish().unwrap();
+    let back: Vec<_> = TokenReader::new(&bytes[..])
+        .unwrap()
+        .collect::<io::Result<_>>()
+        .unwrap();
+    assert_eq!(back, tokens);
+
+    let mut stale = bytes.clone();
+    stale[4] += 1;
+    let err = TokenReader::new(&stale[..]).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let mut writer = TokenWriter::new(vec![]).unwrap();
+    writer.write(&tokens[0]).unwrap();
+    let valid = writer.finish().unwrap();
+    let huge_len = [
+        5, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0,
+    ];
+    let huge_delta = [
+        5, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0, 0,
+    ];
+    let overlong_len = [
+        5, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02, 0,
+    ];
+    let str_and_char = [5, 0, 2, 0, 3];
+    let unknown_flag = [5, 0, 2, 0, 8];
+    let bodies: [&[u8]; 5] = [
+        &huge_len,
+        &huge_delta,
+        &overlong_len,
+        &str_and_char,
+        &unknown_flag,
+    ];
+    for body in bodies {
+        let corrupt = [&valid[..], body].concat();
+        let mut reader = TokenReader::new(&corrupt[..]).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    let mut truncated = TokenReader::new(&bytes[..bytes.len() - 1]).unwrap();
+    assert!(truncated.any(|tok| tok.is_err()));
+    assert!(truncated.next().is_none());
+}
+
+#[test]
+fn trivia_lossless() {
+    let config = || {
+        LexerConfig::new()
+            .line_comment("//")
+            .block_comment("/*", "*/")
+    };
+    let source = "  a = 1; // one\n\n  /* two */ b @@ \"x\n\t1x2 0b \n";
+    let tokens: Vec<_> = TriviaLexer::with_config(source.as_bytes(), config()).collect();
+
+    let text: String = tokens.iter().map(|tok| tok.to_string()).collect();
+    assert_eq!(text, source);
+    let spans: Vec<_> = tokens.iter().map(TriviaToken::full_span).collect();
+    assert!(spans.windows(2).all(|pair| pair[0].end == pair[1].start));
+    assert_eq!(spans.last().unwrap().end, source.len());
+
+    let semi = &tokens[3];
+    assert_eq!(semi.token.value, ";");
+    assert_eq!(
+        semi.trailing
+            .iter()
+            .map(|tok| tok.value.as_ref())
+            .collect::<Vec<_>>(),
+        [" ", "// one"]
+    );
+    assert_eq!(
+        tokens[4]
+            .leading
+            .iter()
+            .map(|tok| tok.value.as_ref())
+            .collect::<Vec<_>>(),
+        ["\n\n  ", "/* two */", " "]
+    );
+    assert_eq!(tokens.last().unwrap().token.kind, TokenKind::Null);
+
+    let config = LexerConfig::new().newlines(Newlines::Split);
+    let tokens: Vec<_> = TriviaLexer::with_config(&b"a \n  b"[..], config).collect();
+    assert_eq!(
+        tokens.iter().map(|tok| tok.token.kind).collect::<Vec<_>>(),
+        [
+            TokenKind::Identifier,
+            TokenKind::Newline,
+            TokenKind::Identifier,
+            TokenKind::Null
+        ]
+    );
+    assert_eq!(tokens[0].to_string(), "a ");
+    assert_eq!(tokens[2].to_string(), "  b");
+}
+
+#[test]
+fn newline_tokens() {
+    let source = b"a \r\n\n  \r\nb\rc\n";
+    let lex = |newlines| {
+        Lexer::with_config(&source[..], LexerConfig::new().newlines(newlines))
+            .map(|tok| (tok.kind, tok.value.into_owned()))
+            .collect::<Vec<_>>()
+    };
+    let tok = |kind, value: &str| (kind, value.to_string());
+
+    assert_eq!(
+        lex(Newlines::Split),
+        [
+            tok(TokenKind::Identifier, "a"),
+            tok(TokenKind::Whitespace, " "),
+            tok(TokenKind::Newline, "\r\n"),
+            tok(TokenKind::Newline, "\n"),
+            tok(TokenKind::Whitespace, "  "),
+            tok(TokenKind::Newline, "\r\n"),
+            tok(TokenKind::Identifier, "b"),
+            tok(TokenKind::Whitespace, "\r"),
+            tok(TokenKind::Identifier, "c"),
+            tok(TokenKind::Newline, "\n"),
+        ]
+    );
+    assert_eq!(
+        lex(Newlines::Merge)[2..4],
+        [
+            tok(TokenKind::Newline, "\r\n\n  \r\n"),
+            tok(TokenKind::Identifier, "b"),
+        ]
+    );
+    assert_eq!(
+        lex(Newlines::Whitespace)[1],
+        tok(TokenKind::Whitespace, " \r\n\n  \r\n")
+    );
+
+    let config = LexerConfig::new()
+        .line_comment("//")
+        .emit_comments(true)
+        .newlines(Newlines::Split);
+    let tokens: Vec<_> = Lexer::with_config(&b"a // c\r\nb"[..], config)
+        .map(|tok| (tok.kind, tok.value.into_owned()))
+        .collect();
+    assert_eq!(
+        tokens[2..4],
+        [
+            tok(TokenKind::Comment, "// c"),
+            tok(TokenKind::Newline, "\r\n"),
+        ]
+    );
+
+    let config = LexerConfig::new()
+        .whitespace(|ch| ch == ' ')
+        .newlines(Newlines::Split)
+        .recover(true);
+    let tokens: Vec<_> = Lexer::with_config("a §\nb".as_bytes(), config)
+        .map(|tok| (tok.kind, tok.value.into_owned()))
+        .collect();
+    assert_eq!(
+        tokens[2..4],
+        [tok(TokenKind::Invalid, "§"), tok(TokenKind::Newline, "\n")]
+    );
+}
+
+#[test]
+fn token_kind_names() {
+    let kinds = [
+        TokenKind::Whitespace,
+        TokenKind::Newline,
+        TokenKind::Invalid,
+        TokenKind::Null,
+        TokenKind::Int,
+        TokenKind::Float,
+        TokenKind::Identifier,
+        TokenKind::Keyword(0),
+        TokenKind::Ponct,
+        TokenKind::Op,
+        TokenKind::String,
+        TokenKind::Char,
+        TokenKind::Comment,
+        TokenKind::DocComment,
+    ];
+    // Stops compiling when a variant is added without listing it above.
+    match kinds[0] {
+        TokenKind::Whitespace
+        | TokenKind::Newline
+        | TokenKind::Invalid
+        | TokenKind::Null
+        | TokenKind::Int
+        | TokenKind::Float
+        | TokenKind::Identifier
+        | TokenKind::Keyword(_)
+        | TokenKind::Ponct
+        | TokenKind::Op
+        | TokenKind::String
+        | TokenKind::Char
+        | TokenKind::Comment
+        | TokenKind::DocComment => {}
+    }
+
+    assert_eq!(kinds.map(TokenKind::name), TokenKind::NAMES);
+}
@@ -0,0 +1,177 @@
+use std::{borrow::Cow, fmt, iter::FusedIterator};
+
+use crate::{LexError, Lexer, LexerConfig, SourceMap, Span, Token, TokenKind};
+
+/// A significant token with the whitespace and comments around it.
+///
+/// Trailing trivia runs up to the end of the token's line; the line break
+/// and everything after it lead the next token. Trivia at the end of the
+/// input leads the final `Null` token.
+///
+/// With [`Newlines::Split`] or [`Newlines::Merge`], line breaks are
+/// significant `Newline` tokens rather than trivia.
+///
+/// [`Newlines::Split`]: crate::Newlines::Split
+/// [`Newlines::Merge`]: crate::Newlines::Merge
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct TriviaToken<'a> {
+    pub leading: Vec<Token<'a>>,
+    pub token: Token<'a>,
+    pub trailing: Vec<Token<'a>>,
+}
+
+impl TriviaToken<'_> {
+    /// Span of the token and its trivia.
+    pub fn full_span(&self) -> Span {
+        let start = self.leading.first().unwrap_or(&self.token).span;
+        let end = self.trailing.last().unwrap_or(&self.token).span;
+        start.to(end)
+    }
+
+    /// Whitespace and comments; `Newline` tokens are significant.
+    pub fn is_trivia(kind: TokenKind) -> bool {
+        matches!(
+            kind,
+            TokenKind::Whitespace | TokenKind::Comment | TokenKind::DocComment
+        )
+    }
+}
+
+/// Writes the text of the token and its trivia, which for valid UTF-8 input
+/// is the exact source of [`TriviaToken::full_span`].
+impl fmt::Display for TriviaToken<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tokens = self
+            .leading
+            .iter()
+            .chain([&self.token])
+            .chain(&self.trailing);
+        for tok in tokens {
+            f.write_str(&tok.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lossless lexer for formatters: every byte of the input belongs to exactly
+/// one token or trivia, in order, so concatenating the full spans of all
+/// tokens reproduces the input.
+///
+/// Comments, error recovery and the final `Null` token are always enabled.
+pub struct TriviaLexer<'a> {
+    lexer: Lexer<'a>,
+    leading: Vec<Token<'a>>,
+    lookahead: Option<Token<'a>>,
+    finished: bool,
+}
+
+impl<'a> TriviaLexer<'a> {
+    pub fn new(input: impl Into<Cow<'a, [u8]>>) -> Self {
+        Self::with_config(input, LexerConfig::default())
+    }
+
+    pub fn with_config(input: impl Into<Cow<'a, [u8]>>, config: LexerConfig) -> Self {
+        let config = config.emit_comments(true).recover(true).emit_eof(true);
+        Self {
+            lexer: Lexer::with_config(input, config),
+            leading: vec![],
+            lookahead: None,
+            finished: false,
+        }
+    }
+
+    pub fn source_map(&self) -> SourceMap<'_> {
+        self.lexer.source_map()
+    }
+
+    /// Errors found so far, in input order.
+    pub fn diagnostics(&self) -> &[LexError] {
+        self.lexer.diagnostics()
+    }
+
+    pub fn take_diagnostics(&mut self) -> Vec<LexError> {
+        self.lexer.take_diagnostics()
+    }
+
+    fn next_raw(&mut self) -> Option<Token<'a>> {
+        self.lookahead.take().or_else(|| self.lexer.next())
+    }
+
+    /// Collects the trivia following the last token on its line.
+    fn trailing(&mut self) -> Vec<Token<'a>> {
+        let mut trailing = vec![];
+
+        while let Some(tok) = self.next_raw() {
+            if !TriviaToken::is_trivia(tok.kind) {
+                self.lookahead = Some(tok);
+                break;
+            }
+            match tok.value.find('\n') {
+                Some(at) if at > 0 && tok.kind == TokenKind::Whitespace => {
+                    let (before, after) = split(tok, at);
+                    trailing.push(before);
+                    self.leading.push(after);
+                }
+                Some(_) => self.leading.push(tok),
+                None => {
+                    trailing.push(tok);
+                    continue;
+                }
+            }
+            break;
+        }
+
+        trailing
+    }
+}
+
+/// Splits `tok` at byte `at` of its text.
+fn split(tok: Token<'_>, at: usize) -> (Token<'_>, Token<'_>) {
+    let (before, after) = match tok.value {
+        Cow::Borrowed(value) => (Cow::Borrowed(&value[..at]), Cow::Borrowed(&value[at..])),
+        Cow::Owned(value) => (
+            Cow::Owned(value[..at].to_string()),
+            Cow::Owned(value[at..].to_string()),
+        ),
+    };
+    let middle = tok.span.start + at;
+
+    (
+        Token::new(tok.kind, before, Span::new(tok.span.start, middle)),
+        Token::new(tok.kind, after, Span::new(middle, tok.span.end)),
+    )
+}
+
+impl<'a> Iterator for TriviaLexer<'a> {
+    type Item = TriviaToken<'a>;
+
+    fn next(&mut self) -> Option<TriviaToken<'a>> {
+        if self.finished {
+            return None;
+        }
+
+        let mut leading = std::mem::take(&mut self.leading);
+        let token = loop {
+            let tok = self.next_raw()?;
+            if !TriviaToken::is_trivia(tok.kind) {
+                break tok;
+            }
+            leading.push(tok);
+        };
+
+        self.finished = token.kind == TokenKind::Null;
+        let trailing = match token.kind {
+            // Nothing follows either on the same line.
+            TokenKind::Null | TokenKind::Newline => vec![],
+            _ => self.trailing(),
+        };
+
+        Some(TriviaToken {
+            leading,
+            token,
+            trailing,
+        })
+    }
+}
+
+impl FusedIterator for TriviaLexer<'_> {}